@@ -1,9 +1,27 @@
+use crate::backup::{self, Backup};
+use crate::creation::{CreationDraft, CreationStep};
+use crate::dice;
+use crate::encounter::{self, Encounter};
 use crate::logic;
-use crate::message::{AttributeField, Message};
-use crate::model::{Ability, Character, Origin};
+use crate::history::History;
+use crate::message::{AttributeField, EditGroup, ItemField, Message, ModifierOwner, PurchaseKind};
+use crate::model::{
+    Ability, ArmourProfile, Character, Condition, ConditionDuration, DurationKind, Item,
+    LedgerEntry, Loadout, Modifier, RollLogEntry, SessionLogEntry, StatTarget, WeaponProfile, Wound,
+    WoundHealing,
+};
+use crate::origins::{self, OriginDef};
 use crate::parser;
-use iced::Task;
+use crate::recovery::{self, RecoverySnapshot};
+use crate::rest::{self, RestRule};
+use crate::salvage;
+use crate::timestamp;
+use iced::keyboard;
+use iced::window;
+use iced::{Subscription, Task};
 use iced::widget::text_editor;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
 use rfd::AsyncFileDialog;
 use std::fs;
 use std::path::PathBuf;
@@ -13,18 +31,26 @@ struct AppConfig {
     last_file_path: Option<PathBuf>,
 }
 
-fn get_config_path() -> PathBuf {
+fn get_state_path(file_name: &str) -> PathBuf {
     if let Some(proj_dirs) = directories::ProjectDirs::from("", "", "BastionCharacterSheet") {
         let dir = proj_dirs.state_dir().unwrap_or_else(|| proj_dirs.data_local_dir());
         std::fs::create_dir_all(dir).ok();
         let mut path = dir.to_path_buf();
-        path.push("config.json");
+        path.push(file_name);
         path
     } else {
-        PathBuf::from("bastion_sheet_config.json")
+        PathBuf::from(format!("bastion_sheet_{}", file_name))
     }
 }
 
+fn get_config_path() -> PathBuf {
+    get_state_path("config.json")
+}
+
+fn get_recovery_path() -> PathBuf {
+    get_state_path("recovery.json")
+}
+
 fn load_config() -> AppConfig {
     if let Ok(content) = fs::read_to_string(get_config_path()) {
         serde_json::from_str(&content).unwrap_or_default()
@@ -48,32 +74,97 @@ pub struct CharacterSheet {
     pub spells_input: String,
     pub miracles_input: String,
     pub level_input: String,
-    pub tender_input: String,
     pub armor_bonus_input: String,
-    pub dr_input: String,
+    pub dr_qualifier_input: String,
     pub bg_color_input: String,
     pub fg_color_input: String,
     pub accent_color_input: String,
-    pub max_hp_offset_input: String,
-    pub speed_offset_input: String,
-    pub max_inventory_slots_offset_input: String,
-    pub max_abilities_offset_input: String,
-    pub max_spells_offset_input: String,
-    pub max_miracles_offset_input: String,
-    pub crit_range_offset_input: String,
     pub ability_body_editors: Vec<text_editor::Content>,
     pub ability_desc_editors: Vec<text_editor::Content>,
-    pub inventory_editors: Vec<text_editor::Content>,
+    pub is_editing_inventory: bool,
+    pub is_editing_conditions: bool,
     pub deleting_ability_index: Option<usize>,
     pub error_message: Option<String>,
     pub notification: Option<String>,
     pub current_file_path: Option<std::path::PathBuf>,
     pub show_save_menu: bool,
+    pub show_validation: bool,
     pub show_ability_browser: bool,
     pub is_editing_abilities: bool,
     pub available_abilities: Vec<Ability>,
+    /// Built-in origins plus any loaded from origin data files.
+    pub origins: Vec<OriginDef>,
     pub ability_search_query: String,
     pub ability_selected_tags: std::collections::HashMap<String, crate::model::TagFilterState>,
+    pub dice_input: String,
+    pub last_roll: Option<dice::RollResult>,
+    pub dice_error: Option<String>,
+    pub rng: StdRng,
+    pub confirming_clear_roll_log: bool,
+    pub buy_name_input: String,
+    pub buy_price_input: String,
+    pub buy_kind: PurchaseKind,
+    pub ledger_amount_input: String,
+    pub ledger_reason_input: String,
+    pub ledger_error: Option<String>,
+    pub xp_input: String,
+    pub xp_thresholds_input: String,
+    pub level_up: Option<Vec<AttributeField>>,
+    pub creation: Option<CreationDraft>,
+    pub creation_budget_input: String,
+    pub creation_tender_input: String,
+    pub creation_item_input: String,
+    pub creation_errors: Vec<String>,
+    pub rest_rules: Vec<RestRule>,
+    pub damage_input: String,
+    pub damage_note_input: String,
+    pub encounter_error: Option<String>,
+    pub ability_use_error: Option<(usize, String)>,
+    pub prepare_error: Option<String>,
+    /// Indices of the abilities picked while choosing a loadout.
+    pub loadout_selection: Option<Vec<usize>>,
+    pub loadout_name_input: String,
+    pub wound_input: String,
+    pub wound_healing: WoundHealing,
+    pub history: History<Character, EditGroup>,
+    /// The character as last loaded or saved, to tell when there are unsaved changes.
+    pub saved_character: Character,
+    pub dirty: bool,
+    /// Action waiting on the Save / Discard / Cancel prompt.
+    pub unsaved_prompt: Option<PendingAction>,
+    /// Action to carry on with once the character has been saved.
+    pub after_save: Option<PendingAction>,
+    /// Unsaved edits from a previous session, waiting to be restored or discarded.
+    pub recovery: Option<RecoverySnapshot>,
+    /// Whether the last autosave failed, so a failure is reported only once.
+    pub autosave_failed: bool,
+    /// Earlier versions of the current file, while the history browser is open.
+    pub version_history: Option<Vec<Backup>>,
+    pub selected_version: Option<(usize, Character)>,
+    /// A damaged file that was only partly loaded, with the JSON paths dropped.
+    pub salvage_report: Option<(PathBuf, Vec<String>)>,
+}
+
+/// Something that would replace or close the current character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PendingAction {
+    Close(window::Id),
+    Load,
+    New,
+}
+
+impl std::fmt::Display for PendingAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PendingAction::Close(_) => "closing",
+                PendingAction::Load => "loading another character",
+                PendingAction::New => "creating a new character",
+            }
+        )
+    }
 }
 
 impl Default for CharacterSheet {
@@ -87,19 +178,11 @@ impl Default for CharacterSheet {
             .saturating_sub(character.expended_miracle_slots)
             .to_string();
         let level = character.level.to_string();
-        let tender = character.tender.to_string();
         let armor_bonus = character.armor_bonus.to_string();
-        let dr_input = character.dr.clone();
+        let dr_qualifier_input = character.dr_qualifier.clone();
         let bg_color_input = character.background_color.clone();
         let fg_color_input = character.foreground_color.clone();
         let accent_color_input = character.accent_color.clone();
-        let max_hp_offset = character.max_hp_offset.to_string();
-        let speed_offset = character.speed_offset.to_string();
-        let max_inventory_slots_offset = character.max_inventory_slots_offset.to_string();
-        let max_abilities_offset = character.max_abilities_offset.to_string();
-        let max_spells_offset = character.max_spells_offset.to_string();
-        let max_miracles_offset = character.max_miracles_offset.to_string();
-        let crit_range_offset = character.crit_range_offset.to_string();
         let ability_body_editors = character
             .abilities
             .iter()
@@ -116,12 +199,7 @@ impl Default for CharacterSheet {
             ab.body_spans = parser::process_text(&ab.body);
             ab.desc_spans = parser::process_text(&ab.desc);
         }
-        
-        let inventory_editors = character_with_spans
-            .inventory
-            .iter()
-            .map(|i| text_editor::Content::with_text(i))
-            .collect();
+        let saved_character = character_with_spans.clone();
 
         Self {
             character: character_with_spans,
@@ -131,32 +209,66 @@ impl Default for CharacterSheet {
             spells_input: spells,
             miracles_input: miracles,
             level_input: level,
-            tender_input: tender,
             armor_bonus_input: armor_bonus,
-            dr_input,
+            dr_qualifier_input,
             bg_color_input,
             fg_color_input,
             accent_color_input,
-            max_hp_offset_input: max_hp_offset,
-            speed_offset_input: speed_offset,
-            max_inventory_slots_offset_input: max_inventory_slots_offset,
-            max_abilities_offset_input: max_abilities_offset,
-            max_spells_offset_input: max_spells_offset,
-            max_miracles_offset_input: max_miracles_offset,
-            crit_range_offset_input: crit_range_offset,
             ability_body_editors,
             ability_desc_editors,
-            inventory_editors,
+            is_editing_inventory: false,
+            is_editing_conditions: false,
             deleting_ability_index: None,
             error_message: None,
             notification: None,
             current_file_path: None,
             show_save_menu: false,
+            show_validation: false,
             show_ability_browser: false,
             is_editing_abilities: false,
             available_abilities: Vec::new(),
+            origins: origins::builtin_origins(),
             ability_search_query: String::new(),
             ability_selected_tags: std::collections::HashMap::new(),
+            dice_input: "1d12".to_string(),
+            last_roll: None,
+            dice_error: None,
+            rng: StdRng::from_os_rng(),
+            confirming_clear_roll_log: false,
+            buy_name_input: String::new(),
+            buy_price_input: String::new(),
+            buy_kind: PurchaseKind::Gear,
+            ledger_amount_input: String::new(),
+            ledger_reason_input: String::new(),
+            ledger_error: None,
+            xp_input: String::new(),
+            xp_thresholds_input: String::new(),
+            level_up: None,
+            creation: None,
+            creation_budget_input: String::new(),
+            creation_tender_input: String::new(),
+            creation_item_input: String::new(),
+            creation_errors: Vec::new(),
+            rest_rules: rest::builtin_rest_rules(),
+            damage_input: String::new(),
+            damage_note_input: String::new(),
+            encounter_error: None,
+            ability_use_error: None,
+            prepare_error: None,
+            loadout_selection: None,
+            loadout_name_input: String::new(),
+            wound_input: String::new(),
+            wound_healing: WoundHealing::default(),
+            history: History::default(),
+            saved_character,
+            dirty: false,
+            unsaved_prompt: None,
+            after_save: None,
+            recovery: None,
+            autosave_failed: false,
+            version_history: None,
+            selected_version: None,
+            salvage_report: None,
         }
     }
 }
@@ -187,14 +299,74 @@ async fn load_abilities_task() -> Vec<Ability> {
     abilities
 }
 
+/// Loads `rest.json` from the working directory or the config directory,
+/// falling back to the built-in rules when neither holds a valid file.
+async fn load_rest_rules_task() -> Vec<RestRule> {
+    let paths = [
+        std::env::current_dir().unwrap_or_default().join("rest.json"),
+        get_config_path()
+            .parent()
+            .unwrap_or(&std::path::PathBuf::from(""))
+            .join("rest.json"),
+    ];
+    paths
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .find_map(|content| rest::parse_rest_rules(&content).ok())
+        .unwrap_or_else(rest::builtin_rest_rules)
+}
+
+async fn load_origins_task() -> Vec<OriginDef> {
+    load_custom_origins()
+}
+
+/// Reads homebrew origin files from `origins/` next to the working directory
+/// and in the config directory.
+pub fn load_custom_origins() -> Vec<OriginDef> {
+    let mut defs = Vec::new();
+    let dirs = [
+        std::env::current_dir().unwrap_or_default().join("origins"),
+        get_config_path()
+            .parent()
+            .unwrap_or(&std::path::PathBuf::from(""))
+            .join("origins"),
+    ];
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if entry.path().extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            if let Ok(content) = std::fs::read_to_string(entry.path())
+                && let Ok(parsed) = origins::parse_origins(&content)
+            {
+                defs.extend(parsed);
+            }
+        }
+    }
+    defs
+}
+
 impl CharacterSheet {
     pub fn new() -> (Self, Task<Message>) {
         let mut sheet = Self::default();
-        sheet.sync_inventory_editors();
+        sheet.sync_inputs();
 
         let config = load_config();
         
-        let load_abs_task = Task::perform(load_abilities_task(), Message::AbilitiesLoaded);
+        let load_abs_task = Task::batch(vec![
+            Task::perform(load_abilities_task(), Message::AbilitiesLoaded),
+            Task::perform(load_origins_task(), Message::OriginsLoaded),
+            Task::perform(load_rest_rules_task(), Message::RestRulesLoaded),
+        ]);
+
+        // Offer unsaved edits from a previous session before opening the last file.
+        if let Some(snapshot) = recovery::take_pending(&get_recovery_path()) {
+            sheet.recovery = Some(snapshot);
+            return (sheet, load_abs_task);
+        }
 
         if let Some(path) = config.last_file_path {
             return (
@@ -209,10 +381,96 @@ impl CharacterSheet {
         (sheet, load_abs_task)
     }
 
+    /// Handles a message, recording the character as it was beforehand when
+    /// the message changes it so the change can be undone.
     pub fn update(&mut self, message: Message) -> Task<Message> {
+        if message.is_ui_only() {
+            return self.handle(message);
+        }
+        if matches!(message, Message::Undo | Message::Redo) {
+            let task = self.handle(message);
+            self.update_dirty();
+            return task;
+        }
+
+        // Loading or creating a character starts a fresh history.
+        let replaces_character = matches!(
+            message,
+            Message::LoadFileSelected(_)
+                | Message::CreationFinish
+                | Message::RestoreRecovery
+                | Message::DiscardRecovery
+        );
+        let group = message.edit_group();
+        let before = self.character.clone();
+        let task = self.handle(message);
+        if before != self.character {
+            if replaces_character {
+                self.history.clear();
+            } else {
+                self.history.record(before, group);
+            }
+            self.update_dirty();
+        }
+        task
+    }
+
+    /// Window title, with a leading "*" while there are unsaved changes.
+    pub fn title(&self) -> String {
+        let name = match &self.current_file_path {
+            Some(path) => path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            None => "Unsaved character".to_string(),
+        };
+        format!(
+            "{}{} - Bastion Character Sheet",
+            if self.dirty { "*" } else { "" },
+            name
+        )
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        let shortcuts = keyboard::listen().filter_map(|event| match event {
+            keyboard::Event::KeyPressed {
+                key: keyboard::Key::Character(c),
+                modifiers,
+                ..
+            } if modifiers.command() => {
+                if c.eq_ignore_ascii_case("z") {
+                    Some(if modifiers.shift() {
+                        Message::Redo
+                    } else {
+                        Message::Undo
+                    })
+                } else if c.eq_ignore_ascii_case("y") {
+                    Some(Message::Redo)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        });
+        Subscription::batch([
+            shortcuts,
+            window::close_requests().map(Message::CloseRequested),
+            iced::time::every(recovery::AUTOSAVE_INTERVAL).map(|_| Message::Autosave),
+        ])
+    }
+
+    fn handle(&mut self, message: Message) -> Task<Message> {
         match message {
+            Message::Undo => {
+                if let Some(previous) = self.history.undo(self.character.clone()) {
+                    self.set_character(previous);
+                }
+            }
+            Message::Redo => {
+                if let Some(next) = self.history.redo(self.character.clone()) {
+                    self.set_character(next);
+                }
+            }
             Message::ToggleEditor => self.is_editing = !self.is_editing,
             Message::ToggleSaveMenu => self.show_save_menu = !self.show_save_menu,
+            Message::ToggleValidationReport => self.show_validation = !self.show_validation,
             Message::NameChanged(name) => self.character.name = name,
             Message::LevelChanged(lvl) => {
                 self.level_input = lvl;
@@ -220,21 +478,18 @@ impl CharacterSheet {
                     self.character.level = val.clamp(1, 10);
                 }
             }
-            Message::TenderChanged(val) => {
-                self.tender_input = val;
-                if let Ok(num) = self.tender_input.parse::<i32>() {
-                    self.character.tender = num;
-                }
-            }
             Message::ArmorBonusChanged(val) => {
                 self.armor_bonus_input = val;
                 if let Ok(num) = self.armor_bonus_input.parse::<i32>() {
                     self.character.armor_bonus = num;
                 }
             }
-            Message::DrChanged(val) => {
-                self.dr_input = val.clone();
-                self.character.dr = val;
+            Message::DrQualifierChanged(val) => {
+                self.dr_qualifier_input = val;
+                let qualifier = self.dr_qualifier_input.trim();
+                if logic::is_valid_dr_qualifier(qualifier) {
+                    self.character.dr_qualifier = qualifier.to_string();
+                }
             }
             Message::BgColorChanged(val) => {
                 self.bg_color_input = val.clone();
@@ -248,67 +503,64 @@ impl CharacterSheet {
                 self.accent_color_input = val.clone();
                 self.character.accent_color = val;
             }
-            Message::OffsetChanged(field, val) => {
-                let parsed = if val.trim().is_empty() || val.trim() == "+" {
-                    Ok(0)
-                } else if val.trim() == "-" {
-                    // Allow typing negative numbers
-                    Err(())
-                } else {
-                    val.parse::<i32>().map_err(|_| ())
+            Message::AddModifier(owner) => {
+                let source = match owner {
+                    ModifierOwner::Character => String::new(),
+                    ModifierOwner::Item(i) => self
+                        .character
+                        .inventory
+                        .get(i)
+                        .map(|item| item.name.clone())
+                        .unwrap_or_default(),
+                    ModifierOwner::Ability(i) => self
+                        .character
+                        .abilities
+                        .get(i)
+                        .map(|ability| ability.name.clone())
+                        .unwrap_or_default(),
+                    ModifierOwner::Condition(i) => self
+                        .character
+                        .conditions
+                        .get(i)
+                        .map(|condition| condition.name.clone())
+                        .unwrap_or_default(),
                 };
-
-                match field {
-                    crate::message::OffsetField::MaxHp => {
-                        self.max_hp_offset_input = val;
-                        if let Ok(num) = parsed {
-                            self.character.max_hp_offset = num;
-                        }
-                    }
-                    crate::message::OffsetField::Speed => {
-                        self.speed_offset_input = val;
-                        if let Ok(num) = parsed {
-                            self.character.speed_offset = num;
-                        }
-                    }
-                    crate::message::OffsetField::MaxInventorySlots => {
-                        self.max_inventory_slots_offset_input = val;
-                        if let Ok(num) = parsed {
-                            self.character.max_inventory_slots_offset = num;
-                            self.sync_inventory_editors();
-                        }
-                    }
-                    crate::message::OffsetField::MaxAbilities => {
-                        self.max_abilities_offset_input = val;
-                        if let Ok(num) = parsed {
-                            self.character.max_abilities_offset = num;
-                        }
-                    }
-                    crate::message::OffsetField::MaxSpells => {
-                        self.max_spells_offset_input = val;
-                        if let Ok(num) = parsed {
-                            self.character.max_spells_offset = num;
-                        }
-                    }
-                    crate::message::OffsetField::MaxMiracles => {
-                        self.max_miracles_offset_input = val;
-                        if let Ok(num) = parsed {
-                            self.character.max_miracles_offset = num;
-                        }
-                    }
-                    crate::message::OffsetField::CritRange => {
-                        self.crit_range_offset_input = val;
-                        if let Ok(num) = parsed {
-                            self.character.crit_range_offset = num;
-                        }
-                    }
+                if let Some(modifiers) = self.modifiers_mut(owner) {
+                    modifiers.push(Modifier::new(source, StatTarget::MaxHp, 1));
+                }
+            }
+            Message::RemoveModifier(owner, idx) => {
+                if let Some(modifiers) = self.modifiers_mut(owner)
+                    && idx < modifiers.len()
+                {
+                    modifiers.remove(idx);
+                }
+            }
+            Message::ModifierSourceChanged(owner, idx, val) => {
+                if let Some(modifier) = self.modifier_mut(owner, idx) {
+                    modifier.source = val;
+                }
+            }
+            Message::ModifierTargetSelected(owner, idx, target) => {
+                if let Some(modifier) = self.modifier_mut(owner, idx) {
+                    modifier.target = target;
+                }
+            }
+            Message::ModifierValueChanged(owner, idx, value) => {
+                if let Some(modifier) = self.modifier_mut(owner, idx) {
+                    modifier.value = value;
+                }
+            }
+            Message::ToggleModifier(owner, idx, active) => {
+                if let Some(modifier) = self.modifier_mut(owner, idx) {
+                    modifier.active = active;
                 }
             }
 
             Message::HpInputChanged(val) => {
                 self.hp_input = val;
                 if let Ok(num) = self.hp_input.parse::<i32>() {
-                    let max = logic::calculate_max_hp(&self.character);
+                    let max = logic::calculate_max_hp(&self.character, &self.origins);
                     self.character.current_hp = num.clamp(0, max);
                 }
             }
@@ -317,7 +569,7 @@ impl CharacterSheet {
             }
             Message::ApplyHpModifier(sign) => {
                 if let Ok(mod_val) = self.hp_modifier.parse::<i32>() {
-                    let max = logic::calculate_max_hp(&self.character);
+                    let max = logic::calculate_max_hp(&self.character, &self.origins);
                     let current = self.character.current_hp;
                     let new_val = (current + (mod_val * sign)).clamp(0, max);
                     self.character.current_hp = new_val;
@@ -325,12 +577,35 @@ impl CharacterSheet {
                 }
             }
 
-            Message::WoundsChanged(val) => {
-                self.character.wounds = val.clamp(0, 4);
-                let max = logic::calculate_max_hp(&self.character);
-                if self.character.current_hp > max {
-                    self.character.current_hp = max;
-                    self.hp_input = max.to_string();
+            Message::WoundInputChanged(val) => {
+                self.wound_input = val;
+            }
+            Message::WoundHealingSelected(healing) => {
+                self.wound_healing = healing;
+            }
+            Message::AddWound => {
+                let description = match self.wound_input.trim() {
+                    "" => "Wound".to_string(),
+                    text => text.to_string(),
+                };
+                self.log_session(format!("Wounded: {}", description));
+                self.character
+                    .wounds
+                    .push(Wound::new(description, self.wound_healing));
+                self.wound_input.clear();
+                self.clamp_hp_to_max();
+            }
+            Message::TreatWound(idx) => {
+                if let Some(wound) = self.character.wounds.get_mut(idx) {
+                    wound.treated = true;
+                    let entry = format!("Treated wound: {}", wound.description);
+                    self.log_session(entry);
+                }
+            }
+            Message::HealWound(idx) => {
+                if idx < self.character.wounds.len() {
+                    let wound = self.character.wounds.remove(idx);
+                    self.log_session(format!("Healed wound: {}", wound.description));
                 }
             }
 
@@ -365,16 +640,117 @@ impl CharacterSheet {
                 self.miracles_input = new_avail.to_string();
             }
 
+            Message::TakeRest(idx) => {
+                if let Some(rule) = self.rest_rules.get(idx) {
+                    let summary =
+                        rest::apply_rest(&mut self.character, &self.origins, rule, &mut self.rng);
+                    self.log_session(summary);
+                    self.sync_inputs();
+                }
+            }
+            Message::ToggleEditConditions => {
+                self.is_editing_conditions = !self.is_editing_conditions;
+            }
+            Message::AddCondition => {
+                self.character
+                    .conditions
+                    .push(Condition::new("New Condition", ConditionDuration::Short));
+            }
+            Message::ExpireCondition(idx) => {
+                if idx < self.character.conditions.len() {
+                    let condition = self.character.conditions.remove(idx);
+                    self.log_session(format!("{} ended", condition.name));
+                    self.sync_inputs();
+                    self.clamp_hp_to_max();
+                }
+            }
+            Message::ConditionNameChanged(idx, val) => {
+                if let Some(condition) = self.character.conditions.get_mut(idx) {
+                    condition.name = val;
+                }
+            }
+            Message::ConditionNotesChanged(idx, val) => {
+                if let Some(condition) = self.character.conditions.get_mut(idx) {
+                    condition.notes = val;
+                }
+            }
+            Message::ConditionDurationSelected(idx, kind) => {
+                if let Some(condition) = self.character.conditions.get_mut(idx) {
+                    condition.duration = match kind {
+                        DurationKind::Instantaneous => ConditionDuration::Instantaneous,
+                        DurationKind::Short => ConditionDuration::Short,
+                        DurationKind::Long => ConditionDuration::Long,
+                        DurationKind::Rounds => ConditionDuration::Rounds(1),
+                    };
+                }
+            }
+            Message::ConditionRoundsChanged(idx, rounds) => {
+                if let Some(condition) = self.character.conditions.get_mut(idx) {
+                    condition.duration = ConditionDuration::Rounds(rounds.max(1));
+                }
+            }
+            Message::AdvanceConditions => self.advance_conditions(),
+            Message::StartEncounter => {
+                self.character.encounter = Some(Encounter::default());
+                self.encounter_error = None;
+                self.log_session("Encounter started");
+            }
+            Message::EndEncounter => {
+                if let Some(encounter) = self.character.encounter.take() {
+                    self.log_session(encounter.summary());
+                }
+                self.encounter_error = None;
+            }
+            Message::NextRound => {
+                if let Some(encounter) = &mut self.character.encounter {
+                    encounter.next_round();
+                    self.encounter_error = None;
+                    self.advance_conditions();
+                }
+            }
+            Message::UseAbility(idx) => {
+                self.ability_use_error = self.use_ability(idx).err().map(|e| (idx, e));
+            }
+            Message::DamageInputChanged(val) => {
+                self.damage_input = val;
+            }
+            Message::DamageNoteChanged(val) => {
+                self.damage_note_input = val;
+            }
+            Message::TakeDamage | Message::DealDamage => {
+                let Ok(amount) = self.damage_input.trim().parse::<i32>() else {
+                    self.encounter_error = Some("Enter a whole number of damage.".to_string());
+                    return Task::none();
+                };
+                let dr = logic::calculate_damage_reduction(&self.character);
+                if let Some(encounter) = &mut self.character.encounter {
+                    if matches!(message, Message::TakeDamage) {
+                        let taken = encounter.take_damage(amount.max(0), dr, &self.damage_note_input);
+                        self.character.current_hp = (self.character.current_hp - taken).max(0);
+                        self.hp_input = self.character.current_hp.to_string();
+                    } else {
+                        encounter.deal_damage(amount.max(0), &self.damage_note_input);
+                    }
+                    self.damage_input.clear();
+                    self.damage_note_input.clear();
+                    self.encounter_error = None;
+                }
+            }
+            Message::RestRulesLoaded(rules) => {
+                self.rest_rules = rules;
+            }
+
             Message::OriginSelected(origin) => {
-                self.character.origin = origin;
-                if origin == Origin::Human && self.character.attributes.luck < 3 {
-                    self.character.attributes.luck = 3;
+                let rules = origins::rules(&self.origins, &origin);
+                for field in AttributeField::all() {
+                    let value = self.character.attributes.get_mut(field);
+                    *value = (*value).max(rules.starting_attribute(field));
                 }
-                self.sync_inventory_editors();
+                self.character.origin = origin;
             }
             Message::AttributeChanged(field, val) => {
-                let max = (self.character.level + 3).min(10);
-                let new_val = val.clamp(1, max);
+                let max = logic::max_attribute_value(self.character.level);
+                let new_val = val.clamp(logic::MIN_ATTRIBUTE_VALUE, max);
                 match field {
                     AttributeField::Strength => self.character.attributes.strength = new_val,
                     AttributeField::Dexterity => self.character.attributes.dexterity = new_val,
@@ -386,27 +762,11 @@ impl CharacterSheet {
                     }
                     AttributeField::Luck => self.character.attributes.luck = new_val,
                 }
-                if field == AttributeField::Strength {
-                    self.sync_inventory_editors();
-                }
             }
             Message::SaveCharacter => {
                 self.show_save_menu = false;
-                if let Some(path) = &self.current_file_path {
-                    if let Ok(json) = serde_json::to_string_pretty(&self.character) {
-                        if let Err(e) = fs::write(path, json) {
-                            self.error_message = Some(format!("Could not save file: {}", e));
-                        } else {
-                            let config = AppConfig {
-                                last_file_path: Some(path.clone()),
-                            };
-                            save_config(&config);
-                            self.notification = Some(format!(
-                                "Character successfully saved to {:?}",
-                                path.file_name().unwrap_or_default()
-                            ));
-                        }
-                    }
+                if let Some(path) = self.current_file_path.clone() {
+                    return self.save_to(path);
                 } else {
                     let default_name = format!("{}.json", self.character.name);
                     return Task::perform(
@@ -438,103 +798,121 @@ impl CharacterSheet {
                 );
             }
             Message::LoadCharacter => {
-                return Task::perform(
-                    async {
-                        let file = AsyncFileDialog::new()
-                            .add_filter("json", &["json"])
-                            .pick_file()
-                            .await;
-                        file.map(|f| f.path().to_owned())
-                    },
-                    Message::LoadFileSelected,
-                );
+                return self.guard_unsaved(PendingAction::Load);
             }
             Message::SaveFileSelected(path_opt) => {
                 if let Some(path) = path_opt {
-                    if let Ok(json) = serde_json::to_string_pretty(&self.character) {
-                        if let Err(e) = fs::write(&path, json) {
-                            self.error_message = Some(format!("Could not save file: {}", e));
-                        } else {
-                            self.current_file_path = Some(path.clone());
-                            let config = AppConfig {
-                                last_file_path: Some(path.clone()),
-                            };
-                            save_config(&config);
-                            self.notification = Some(format!(
-                                "Character successfully saved to {:?}",
-                                path.file_name().unwrap_or_default()
-                            ));
+                    return self.save_to(path);
+                }
+                // Cancelling the dialog also cancels whatever was waiting on the save.
+                self.after_save = None;
+            }
+            Message::CloseRequested(id) => {
+                return self.guard_unsaved(PendingAction::Close(id));
+            }
+            Message::SaveBeforeContinuing => {
+                self.after_save = self.unsaved_prompt.take();
+                return self.handle(Message::SaveCharacter);
+            }
+            Message::DiscardChanges => {
+                if let Some(action) = self.unsaved_prompt.take() {
+                    return self.run_action(action);
+                }
+            }
+            Message::ShowVersionHistory => {
+                self.show_save_menu = false;
+                if let Some(path) = &self.current_file_path {
+                    self.version_history = Some(backup::list_backups(path));
+                    self.selected_version = None;
+                }
+            }
+            Message::CloseVersionHistory => {
+                self.version_history = None;
+                self.selected_version = None;
+            }
+            Message::VersionSelected(idx) => {
+                let Some(version) = self.version_history.as_ref().and_then(|v| v.get(idx)) else {
+                    return Task::none();
+                };
+                match version.load() {
+                    Ok(character) => self.selected_version = Some((idx, character)),
+                    Err(e) => {
+                        self.error_message = Some(format!("Could not read backup: {}", e));
+                    }
+                }
+            }
+            Message::RestoreVersion => {
+                if let Some((idx, character)) = self.selected_version.take() {
+                    let saved_at = self
+                        .version_history
+                        .take()
+                        .and_then(|v| v.get(idx).map(|b| b.saved_at))
+                        .unwrap_or_default();
+                    self.set_character(character);
+                    self.notification = Some(format!(
+                        "Restored the version from {}. Save to keep it.",
+                        timestamp::format(saved_at)
+                    ));
+                }
+            }
+            Message::CancelPendingAction => {
+                self.unsaved_prompt = None;
+            }
+            Message::Autosave => {
+                // Nothing to recover once everything is saved.
+                if !self.dirty {
+                    self.clear_recovery_file();
+                } else if self.recovery.is_none() {
+                    let snapshot =
+                        RecoverySnapshot::new(&self.character, self.current_file_path.clone());
+                    match recovery::write(&get_recovery_path(), &snapshot) {
+                        Ok(()) => self.autosave_failed = false,
+                        Err(e) => {
+                            if !self.autosave_failed {
+                                self.error_message =
+                                    Some(format!("Could not autosave unsaved changes: {}", e));
+                            }
+                            self.autosave_failed = true;
                         }
                     }
                 }
             }
+            Message::RestoreRecovery => {
+                if let Some(snapshot) = self.recovery.take() {
+                    // Load the file first so the restored edits show as unsaved changes to it.
+                    if let Some(path) = snapshot.file_path.filter(|p| p.exists()) {
+                        let _ = self.handle(Message::LoadFileSelected(Some(path)));
+                    }
+                    self.set_character(snapshot.character);
+                }
+            }
+            Message::DiscardRecovery => {
+                self.recovery = None;
+                recovery::clear(&get_recovery_path());
+                return self.handle(Message::LoadFileSelected(load_config().last_file_path));
+            }
             Message::LoadFileSelected(path_opt) => {
                 if let Some(path) = path_opt {
                     match fs::read_to_string(&path) {
-                        Ok(content) => match serde_json::from_str::<Character>(&content) {
+                        Ok(content) => match crate::model::character_from_json(&content) {
                             Ok(char) => {
-                                self.character = char;
+                                self.set_character(char);
+                                self.saved_character = self.character.clone();
                                 self.current_file_path = Some(path.clone());
                                 let config = AppConfig {
                                     last_file_path: Some(path.clone()),
                                 };
                                 save_config(&config);
-                                self.hp_input = self.character.current_hp.to_string();
-                                let s_max = logic::calculate_spell_slots(&self.character);
-                                self.spells_input = s_max
-                                    .saturating_sub(self.character.expended_spell_slots)
-                                    .to_string();
-                                let m_max = logic::calculate_miracle_slots(&self.character);
-                                self.miracles_input = m_max
-                                    .saturating_sub(self.character.expended_miracle_slots)
-                                    .to_string();
-                                self.level_input = self.character.level.to_string();
-                                self.tender_input = self.character.tender.to_string();
-                                self.armor_bonus_input = self.character.armor_bonus.to_string();
-                                self.dr_input = self.character.dr.clone();
-                                self.bg_color_input = self.character.background_color.clone();
-                                self.fg_color_input = self.character.foreground_color.clone();
-                                self.accent_color_input = self.character.accent_color.clone();
-                                self.max_hp_offset_input = self.character.max_hp_offset.to_string();
-                                self.speed_offset_input = self.character.speed_offset.to_string();
-                                self.max_inventory_slots_offset_input =
-                                    self.character.max_inventory_slots_offset.to_string();
-                                self.max_abilities_offset_input =
-                                    self.character.max_abilities_offset.to_string();
-                                self.max_spells_offset_input =
-                                    self.character.max_spells_offset.to_string();
-                                self.max_miracles_offset_input =
-                                    self.character.max_miracles_offset.to_string();
-                                self.crit_range_offset_input =
-                                    self.character.crit_range_offset.to_string();
-                                self.ability_body_editors = self
-                                    .character
-                                    .abilities
-                                    .iter()
-                                    .map(|a| text_editor::Content::with_text(&a.body))
-                                    .collect();
-                                self.ability_desc_editors = self
-                                    .character
-                                    .abilities
-                                    .iter()
-                                    .map(|a| text_editor::Content::with_text(&a.desc))
-                                    .collect();
-                                for ab in &mut self.character.abilities {
-                                    ab.body_spans = parser::process_text(&ab.body);
-                                    ab.desc_spans = parser::process_text(&ab.desc);
-                                }
-                                self.inventory_editors = self
-                                    .character
-                                    .inventory
-                                    .iter()
-                                    .map(|i| text_editor::Content::with_text(i))
-                                    .collect();
-                                self.sync_inventory_editors();
-                            }
-                            Err(e) => {
-                                self.error_message =
-                                    Some(format!("Invalid character format: {}", e));
                             }
+                            // Keep what can be read and leave the damaged file alone.
+                            Err(e) => match salvage::salvage_character(&content) {
+                                Ok(salvaged) => {
+                                    self.set_character(salvaged.character);
+                                    self.current_file_path = None;
+                                    self.salvage_report = Some((path.clone(), salvaged.dropped));
+                                }
+                                Err(_) => self.error_message = Some(e),
+                            },
                         },
                         Err(e) => {
                             self.error_message = Some(format!("Could not read file: {}", e));
@@ -542,26 +920,370 @@ impl CharacterSheet {
                     }
                 }
             }
+            Message::DismissSalvageReport => {
+                self.salvage_report = None;
+            }
             Message::DismissError => {
                 self.error_message = None;
             }
             Message::DismissNotification => {
                 self.notification = None;
             }
-            Message::InventoryAction(idx, action) => {
-                while self.inventory_editors.len() <= idx {
-                    self.inventory_editors.push(text_editor::Content::new());
+            Message::ToggleEditInventory => {
+                self.is_editing_inventory = !self.is_editing_inventory;
+            }
+            Message::AddItem => {
+                self.character.inventory.push(Item::new("New Item"));
+            }
+            Message::RemoveItem(idx) => {
+                if idx < self.character.inventory.len() {
+                    self.character.inventory.remove(idx);
                 }
-                if let Some(editor) = self.inventory_editors.get_mut(idx) {
-                    editor.perform(action);
-                    let text = editor.text();
-                    if idx < self.character.inventory.len() {
-                        self.character.inventory[idx] = text;
+            }
+            Message::ItemChanged(idx, field, val) => {
+                if let Some(item) = self.character.inventory.get_mut(idx) {
+                    let number = if val.trim().is_empty() {
+                        Some(0)
+                    } else {
+                        val.trim().parse::<i32>().ok()
+                    };
+                    match field {
+                        ItemField::Name => item.name = val,
+                        ItemField::Tags => item.tags = val,
+                        ItemField::Notes => item.notes = val,
+                        ItemField::Slots => {
+                            if let Some(n) = number {
+                                item.slots = n.max(0);
+                            }
+                        }
+                        ItemField::Quantity => {
+                            if let Some(n) = number {
+                                item.quantity = n.max(0);
+                            }
+                        }
+                        ItemField::Price => {
+                            if let Some(n) = number {
+                                item.price = n.max(0);
+                            }
+                        }
+                        ItemField::Damage => {
+                            if let Some(weapon) = &mut item.weapon {
+                                weapon.damage = val;
+                            }
+                        }
+                        ItemField::HeavyStrength => {
+                            if let (Some(weapon), Some(n)) = (&mut item.weapon, number) {
+                                weapon.heavy = n.max(0);
+                            }
+                        }
+                        ItemField::Properties => {
+                            if let Some(weapon) = &mut item.weapon {
+                                weapon.properties = val;
+                            }
+                        }
+                        ItemField::ArmourAc => {
+                            if let (Some(armour), Some(n)) = (&mut item.armour, number) {
+                                armour.ac = n;
+                            }
+                        }
+                        ItemField::ArmourDr => {
+                            if let (Some(armour), Some(n)) = (&mut item.armour, number) {
+                                armour.dr = n.max(0);
+                            }
+                        }
+                        ItemField::ArmourStrength => {
+                            if let (Some(armour), Some(n)) = (&mut item.armour, number) {
+                                armour.strength = n.max(0);
+                            }
+                        }
+                    }
+                }
+            }
+            Message::ToggleItemWeapon(idx, is_weapon) => {
+                if let Some(item) = self.character.inventory.get_mut(idx) {
+                    item.weapon = is_weapon.then(WeaponProfile::default);
+                }
+            }
+            Message::ToggleItemArmour(idx, is_armour) => {
+                if let Some(item) = self.character.inventory.get_mut(idx) {
+                    item.armour = is_armour.then(ArmourProfile::default);
+                }
+            }
+            Message::ToggleArmourSlow(idx, slow) => {
+                if let Some(armour) = self
+                    .character
+                    .inventory
+                    .get_mut(idx)
+                    .and_then(|item| item.armour.as_mut())
+                {
+                    armour.slow = slow;
+                }
+            }
+            Message::ToggleItemEquipped(idx, equipped) => {
+                if let Some(item) = self.character.inventory.get_mut(idx) {
+                    item.equipped = equipped;
+                }
+            }
+            Message::SellItem(idx) => {
+                if let Some(item) = self.character.inventory.get_mut(idx) {
+                    let name = item.name.clone();
+                    let price = item.price;
+                    if item.quantity > 1 {
+                        item.quantity -= 1;
+                    } else {
+                        self.character.inventory.remove(idx);
+                    }
+                    self.character.ledger.push(LedgerEntry::new(
+                        price,
+                        format!("Sold {}", name),
+                        Some(name),
+                    ));
+                }
+            }
+            Message::BuyNameChanged(val) => {
+                self.buy_name_input = val;
+            }
+            Message::BuyPriceChanged(val) => {
+                self.buy_price_input = val;
+            }
+            Message::BuyKindSelected(kind) => {
+                self.buy_kind = kind;
+            }
+            Message::BuyItem => {
+                let name = self.buy_name_input.trim().to_string();
+                let price = self
+                    .buy_price_input
+                    .trim()
+                    .parse::<i32>()
+                    .ok()
+                    .filter(|p| *p >= 0);
+                if name.is_empty() {
+                    self.ledger_error = Some("Enter a name for the item".to_string());
+                } else if let Some(price) = price {
+                    let mut item = Item::new(name.clone());
+                    item.price = price;
+                    match self.buy_kind {
+                        PurchaseKind::Gear => {}
+                        PurchaseKind::Weapon => item.weapon = Some(WeaponProfile::default()),
+                        PurchaseKind::Armour => item.armour = Some(ArmourProfile::default()),
+                    }
+                    let cost =
+                        logic::calculate_purchase_price(&self.character, &self.origins, &item);
+                    let balance = logic::calculate_tender(&self.character);
+                    if cost > balance {
+                        self.ledger_error = Some(format!(
+                            "Not enough tender: {} costs {}, you have {}",
+                            name, cost, balance
+                        ));
+                    } else {
+                        self.character.ledger.push(LedgerEntry::new(
+                            -cost,
+                            format!("Bought {}", name),
+                            Some(name),
+                        ));
+                        self.character.inventory.push(item);
+                        self.buy_name_input.clear();
+                        self.buy_price_input.clear();
+                        self.ledger_error = None;
+                    }
+                } else {
+                    self.ledger_error = Some("Enter a valid price".to_string());
+                }
+            }
+            Message::LedgerAmountChanged(val) => {
+                self.ledger_amount_input = val;
+            }
+            Message::LedgerReasonChanged(val) => {
+                self.ledger_reason_input = val;
+            }
+            Message::AddLedgerEntry => {
+                match self.ledger_amount_input.trim().trim_start_matches('+').parse::<i32>() {
+                    Ok(amount) if amount != 0 => {
+                        let reason = match self.ledger_reason_input.trim() {
+                            "" if amount > 0 => "Income".to_string(),
+                            "" => "Expense".to_string(),
+                            reason => reason.to_string(),
+                        };
+                        self.character
+                            .ledger
+                            .push(LedgerEntry::new(amount, reason, None));
+                        self.ledger_amount_input.clear();
+                        self.ledger_reason_input.clear();
+                        self.ledger_error = None;
+                    }
+                    _ => {
+                        self.ledger_error =
+                            Some("Enter a non-zero amount, e.g. 25 or -10".to_string());
+                    }
+                }
+            }
+            Message::XpInputChanged(val) => {
+                self.xp_input = val;
+            }
+            Message::AddXp => {
+                if let Ok(amount) = self.xp_input.trim().trim_start_matches('+').parse::<i32>() {
+                    self.character.xp = (self.character.xp + amount).max(0);
+                    self.xp_input.clear();
+                }
+            }
+            Message::XpThresholdsChanged(val) => {
+                let parsed: Result<Vec<i32>, _> = val
+                    .split(',')
+                    .map(|t| t.trim())
+                    .filter(|t| !t.is_empty())
+                    .map(|t| t.parse::<i32>())
+                    .collect();
+                self.xp_thresholds_input = val;
+                if let Ok(thresholds) = parsed
+                    && thresholds.windows(2).all(|w| w[0] < w[1])
+                {
+                    self.character.xp_thresholds = thresholds;
+                }
+            }
+            Message::StartLevelUp => {
+                if logic::can_level_up(&self.character) {
+                    self.level_up = Some(Vec::new());
+                }
+            }
+            Message::LevelUpAttributeChanged(field, selected) => {
+                if let Some(increases) = &mut self.level_up {
+                    if selected {
+                        if (increases.len() as i32) < logic::LEVEL_UP_ATTRIBUTE_POINTS {
+                            increases.push(field);
+                        }
                     } else {
-                        while self.character.inventory.len() <= idx {
-                            self.character.inventory.push(String::new());
+                        increases.retain(|f| *f != field);
+                    }
+                }
+            }
+            Message::ConfirmLevelUp => {
+                if let Some(increases) = self.level_up.take() {
+                    let record =
+                        logic::apply_level_up(&mut self.character, &self.origins, &increases);
+                    self.character.level_history.push(record);
+                    self.sync_inputs();
+                }
+            }
+            Message::CancelLevelUp => {
+                self.level_up = None;
+            }
+            Message::StartCreation => {
+                return self.guard_unsaved(PendingAction::New);
+            }
+            Message::BeginCreation => {
+                let draft = CreationDraft::new(&self.origins);
+                self.creation_budget_input = draft.point_budget.to_string();
+                self.creation_tender_input = draft.starting_tender.to_string();
+                self.creation_item_input.clear();
+                self.creation_errors.clear();
+                self.creation = Some(draft);
+            }
+            Message::CancelCreation => {
+                self.creation = None;
+                self.creation_errors.clear();
+            }
+            Message::CreationBack => {
+                if let Some(draft) = &mut self.creation
+                    && let Some(step) = draft.step.previous()
+                {
+                    draft.step = step;
+                    self.creation_errors.clear();
+                }
+            }
+            Message::CreationNext => {
+                if let Some(draft) = &mut self.creation {
+                    self.creation_errors = draft.validate_step(draft.step);
+                    if self.creation_errors.is_empty()
+                        && let Some(step) = draft.step.next()
+                    {
+                        draft.step = step;
+                    }
+                }
+            }
+            Message::CreationFinish => {
+                if let Some(draft) = &self.creation {
+                    self.creation_errors = draft.validate_step(CreationStep::Review);
+                    if self.creation_errors.is_empty() {
+                        let character = draft.build(&self.origins);
+                        self.creation = None;
+                        self.current_file_path = None;
+                        self.set_character(character);
+                    }
+                }
+            }
+            Message::CreationNameChanged(name) => {
+                if let Some(draft) = &mut self.creation {
+                    draft.name = name;
+                }
+            }
+            Message::CreationOriginSelected(origin) => {
+                if let Some(draft) = &mut self.creation {
+                    draft.set_origin(&self.origins, origin);
+                }
+            }
+            Message::CreationAttributeChanged(field, val) => {
+                if let Some(draft) = &mut self.creation {
+                    draft.set_attribute(field, val);
+                }
+            }
+            Message::CreationBudgetChanged(val) => {
+                self.creation_budget_input = val;
+                if let (Some(draft), Ok(budget)) = (
+                    &mut self.creation,
+                    self.creation_budget_input.trim().parse::<i32>(),
+                ) {
+                    draft.point_budget = budget;
+                }
+            }
+            Message::CreationTenderChanged(val) => {
+                self.creation_tender_input = val;
+                if let (Some(draft), Ok(tender)) = (
+                    &mut self.creation,
+                    self.creation_tender_input.trim().parse::<i32>(),
+                ) {
+                    draft.starting_tender = tender;
+                }
+            }
+            Message::CreationItemInputChanged(val) => {
+                self.creation_item_input = val;
+            }
+            Message::CreationAddItem => {
+                let name = self.creation_item_input.trim().to_string();
+                if let Some(draft) = &mut self.creation
+                    && !name.is_empty()
+                {
+                    draft.items.push(name);
+                    self.creation_item_input.clear();
+                }
+            }
+            Message::CreationRemoveItem(idx) => {
+                if let Some(draft) = &mut self.creation
+                    && idx < draft.items.len()
+                {
+                    draft.items.remove(idx);
+                }
+            }
+            Message::CreationAbilityToggled(ability, selected) => {
+                if let Some(draft) = &mut self.creation {
+                    draft.abilities.retain(|a| a.name != ability.name);
+                    if selected {
+                        draft.abilities.push(ability);
+                    }
+                }
+            }
+            Message::RollWeaponDamage(idx) => {
+                if let Some(item) = self.character.inventory.get(idx)
+                    && let Some(weapon) = &item.weapon
+                {
+                    match dice::parse(&weapon.damage) {
+                        Ok(mut expr) => {
+                            expr.label = Some(format!("{} damage", item.name));
+                            let result = expr.roll(&mut self.rng);
+                            self.record_roll(result);
+                        }
+                        Err(e) => {
+                            self.dice_error = Some(format!("{}: {}", item.name, e));
                         }
-                        self.character.inventory[idx] = text;
                     }
                 }
             }
@@ -574,6 +1296,7 @@ impl CharacterSheet {
                     prepared: false,
                     body_spans: Vec::new(),
                     desc_spans: Vec::new(),
+                    modifiers: Vec::new(),
                 });
                 self.ability_body_editors.push(text_editor::Content::new());
                 self.ability_desc_editors.push(text_editor::Content::new());
@@ -627,10 +1350,83 @@ impl CharacterSheet {
                 }
             }
             Message::ToggleAbilityPrepared(idx, val) => {
-                if let Some(ab) = self.character.abilities.get_mut(idx) {
-                    ab.prepared = val;
+                self.prepare_error = logic::set_prepared(&mut self.character, idx, val).err();
+            }
+            Message::StartLoadoutSelection => {
+                self.loadout_selection = Some(
+                    self.character
+                        .abilities
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, a)| a.prepared)
+                        .map(|(i, _)| i)
+                        .collect(),
+                );
+                self.prepare_error = None;
+            }
+            Message::CancelLoadoutSelection => {
+                self.loadout_selection = None;
+                self.prepare_error = None;
+            }
+            Message::LoadoutSelectionToggled(idx, selected) => {
+                if let Some(selection) = &mut self.loadout_selection {
+                    selection.retain(|i| *i != idx);
+                    if selected {
+                        selection.push(idx);
+                    }
+                }
+            }
+            Message::PrepareSelection => {
+                let names = self.selected_ability_names();
+                match logic::apply_loadout(&mut self.character, &names) {
+                    Ok(missing) => {
+                        self.loadout_selection = None;
+                        self.prepare_error = Self::missing_abilities_notice(&missing);
+                    }
+                    Err(e) => self.prepare_error = Some(e),
+                }
+            }
+            Message::LoadoutNameChanged(val) => {
+                self.loadout_name_input = val;
+            }
+            Message::SaveLoadout => {
+                let name = self.loadout_name_input.trim().to_string();
+                if name.is_empty() {
+                    self.prepare_error = Some("Name the loadout before saving it.".to_string());
+                } else {
+                    let abilities = self.selected_ability_names();
+                    match self.character.loadouts.iter_mut().find(|l| l.name == name) {
+                        Some(existing) => existing.abilities = abilities,
+                        None => self.character.loadouts.push(Loadout { name, abilities }),
+                    }
+                    self.loadout_name_input.clear();
+                    self.prepare_error = None;
                 }
             }
+            Message::LoadoutSelected(name) => {
+                if let Some(loadout) = self.character.loadouts.iter().find(|l| l.name == name) {
+                    let abilities = &self.character.abilities;
+                    self.loadout_selection = Some(
+                        abilities
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, a)| loadout.abilities.contains(&a.name))
+                            .map(|(i, _)| i)
+                            .collect(),
+                    );
+                    let missing: Vec<String> = loadout
+                        .abilities
+                        .iter()
+                        .filter(|n| !abilities.iter().any(|a| &a.name == *n))
+                        .cloned()
+                        .collect();
+                    self.prepare_error = Self::missing_abilities_notice(&missing);
+                    self.loadout_name_input = name;
+                }
+            }
+            Message::DeleteLoadout(name) => {
+                self.character.loadouts.retain(|l| l.name != name);
+            }
             Message::MoveAbilityUp(idx) => {
                 if idx > 0 && idx < self.character.abilities.len() {
                     self.character.abilities.swap(idx, idx - 1);
@@ -666,6 +1462,8 @@ impl CharacterSheet {
             }
             Message::ToggleEditAbilities => {
                 self.is_editing_abilities = !self.is_editing_abilities;
+                self.ability_use_error = None;
+                self.loadout_selection = None;
             }
             Message::AbilityBrowserSearchChanged(query) => {
                 self.ability_search_query = query;
@@ -699,23 +1497,550 @@ impl CharacterSheet {
             Message::AbilitiesLoaded(loaded) => {
                 self.available_abilities = loaded;
             }
+            Message::OriginsLoaded(loaded) => {
+                origins::register(&mut self.origins, loaded);
+            }
+            Message::DiceExpressionChanged(val) => {
+                self.dice_input = val;
+            }
+            Message::RollDice => {
+                let expr = self.dice_input.clone();
+                self.roll_expression(&expr);
+            }
+            Message::RollExpression(expr) => {
+                self.dice_input = expr.clone();
+                self.roll_expression(&expr);
+            }
+            Message::RollAttributeCheck(field) => {
+                let bonus = logic::attribute_value(&self.character, field);
+                let crit_range = logic::calculate_crit_range(&self.character);
+                let label = format!("{} check", field);
+                let result = dice::roll_check(&mut self.rng, bonus, crit_range, &label);
+                self.record_roll(result);
+            }
+            Message::RequestClearRollLog => {
+                self.confirming_clear_roll_log = true;
+            }
+            Message::ConfirmClearRollLog => {
+                self.character.roll_log.clear();
+                self.confirming_clear_roll_log = false;
+            }
+            Message::CancelClearRollLog => {
+                self.confirming_clear_roll_log = false;
+            }
+            Message::ExportRollLog => {
+                let default_name = format!("{} rolls.csv", self.character.name);
+                return Task::perform(
+                    async move {
+                        let file = AsyncFileDialog::new()
+                            .add_filter("csv", &["csv"])
+                            .set_file_name(&default_name)
+                            .save_file()
+                            .await;
+                        file.map(|f| f.path().to_owned())
+                    },
+                    Message::RollLogExportSelected,
+                );
+            }
+            Message::RollLogExportSelected(path_opt) => {
+                if let Some(path) = path_opt {
+                    let csv = crate::model::roll_log_to_csv(&self.character.roll_log);
+                    if let Err(e) = fs::write(&path, csv) {
+                        self.error_message = Some(format!("Could not export roll log: {}", e));
+                    } else {
+                        self.notification = Some(format!(
+                            "Roll log exported to {:?}",
+                            path.file_name().unwrap_or_default()
+                        ));
+                    }
+                }
+            }
         }
         Task::none()
     }
 
-    fn sync_inventory_editors(&mut self) {
-        let total_slots = logic::calculate_carrying_slots(&self.character);
-        let display_count = total_slots.max(self.character.inventory.len() as i32) as usize;
+    fn update_dirty(&mut self) {
+        self.dirty = self.saved_character != self.character;
+    }
+
+    /// Writes the character to `path`, then carries on with any action that
+    /// was waiting for the save.
+    fn save_to(&mut self, path: PathBuf) -> Task<Message> {
+        let result = serde_json::to_string_pretty(&self.character)
+            .map_err(|e| e.to_string())
+            .and_then(|json| backup::save_with_backup(&path, &json, timestamp::now()));
+        if let Err(e) = result {
+            self.error_message = Some(format!("Could not save file: {}", e));
+            self.after_save = None;
+            return Task::none();
+        }
+
+        self.current_file_path = Some(path.clone());
+        self.saved_character = self.character.clone();
+        self.dirty = false;
+        self.clear_recovery_file();
+        let config = AppConfig {
+            last_file_path: Some(path.clone()),
+        };
+        save_config(&config);
+
+        match self.after_save.take() {
+            Some(action) => self.run_action(action),
+            None => {
+                self.notification = Some(format!(
+                    "Character successfully saved to {:?}",
+                    path.file_name().unwrap_or_default()
+                ));
+                Task::none()
+            }
+        }
+    }
+
+    /// Deletes the recovery file, unless it still holds edits from a previous
+    /// session that the user has not yet restored or discarded.
+    fn clear_recovery_file(&self) {
+        if self.recovery.is_none() {
+            recovery::clear(&get_recovery_path());
+        }
+    }
+
+    /// Runs `action` straight away, or asks first when it would throw away
+    /// unsaved changes.
+    fn guard_unsaved(&mut self, action: PendingAction) -> Task<Message> {
+        if self.dirty {
+            self.unsaved_prompt = Some(action);
+            Task::none()
+        } else {
+            self.run_action(action)
+        }
+    }
+
+    fn run_action(&mut self, action: PendingAction) -> Task<Message> {
+        match action {
+            PendingAction::Close(id) => {
+                self.clear_recovery_file();
+                window::close(id)
+            }
+            PendingAction::Load => Task::perform(
+                async {
+                    let file = AsyncFileDialog::new()
+                        .add_filter("json", &["json"])
+                        .pick_file()
+                        .await;
+                    file.map(|f| f.path().to_owned())
+                },
+                Message::LoadFileSelected,
+            ),
+            PendingAction::New => self.handle(Message::BeginCreation),
+        }
+    }
+
+    /// Replaces the character and rebuilds every input and editor derived from it.
+    fn set_character(&mut self, character: Character) {
+        self.character = character;
+        self.deleting_ability_index = None;
+        self.ability_use_error = None;
+        self.loadout_selection = None;
+        self.ability_body_editors = self
+            .character
+            .abilities
+            .iter()
+            .map(|a| text_editor::Content::with_text(&a.body))
+            .collect();
+        self.ability_desc_editors = self
+            .character
+            .abilities
+            .iter()
+            .map(|a| text_editor::Content::with_text(&a.desc))
+            .collect();
+        for ab in &mut self.character.abilities {
+            ab.body_spans = parser::process_text(&ab.body);
+            ab.desc_spans = parser::process_text(&ab.desc);
+        }
+        self.sync_inputs();
+    }
+
+    fn sync_inputs(&mut self) {
+        self.hp_input = self.character.current_hp.to_string();
+        let s_max = logic::calculate_spell_slots(&self.character);
+        self.spells_input = s_max
+            .saturating_sub(self.character.expended_spell_slots)
+            .to_string();
+        let m_max = logic::calculate_miracle_slots(&self.character);
+        self.miracles_input = m_max
+            .saturating_sub(self.character.expended_miracle_slots)
+            .to_string();
+        self.level_input = self.character.level.to_string();
+        self.armor_bonus_input = self.character.armor_bonus.to_string();
+        self.dr_qualifier_input = self.character.dr_qualifier.clone();
+        self.bg_color_input = self.character.background_color.clone();
+        self.fg_color_input = self.character.foreground_color.clone();
+        self.accent_color_input = self.character.accent_color.clone();
+        self.xp_thresholds_input = self
+            .character
+            .xp_thresholds
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+    }
+
+    fn roll_expression(&mut self, expr: &str) {
+        match dice::roll(expr, &mut self.rng) {
+            Ok(result) => self.record_roll(result),
+            Err(e) => {
+                self.dice_error = Some(e);
+            }
+        }
+    }
+
+    fn record_roll(&mut self, result: dice::RollResult) {
+        self.character
+            .roll_log
+            .push(RollLogEntry::new(&result, timestamp::now()));
+        self.last_roll = Some(result);
+        self.dice_error = None;
+    }
+
+    /// Uses a prepared ability: checks and spends its actions and slot, logs
+    /// the use and rolls the first dice expression in its rules text.
+    fn use_ability(&mut self, idx: usize) -> Result<(), String> {
+        let Some(ability) = self.character.abilities.get(idx).cloned() else {
+            return Ok(());
+        };
+        if !ability.prepared {
+            return Err(format!("{} is not prepared.", ability.name));
+        }
+        let slot = logic::ability_slot(&ability);
+
+        if let Some(encounter) = &self.character.encounter {
+            encounter.can_use(&ability)?;
+        }
+        if let Some(kind) = slot
+            && logic::slots_remaining(&self.character, kind) == 0
+        {
+            return Err(format!("No {}s left.", kind));
+        }
+
+        let mut spent = Vec::new();
+        if let Some(encounter) = &mut self.character.encounter {
+            encounter.use_ability(&ability)?;
+            if encounter::is_punish(&ability) {
+                spent.push("reaction".to_string());
+            } else if let Some(cost) = encounter::action_cost(&ability) {
+                spent.push(format!("{} action{}", cost, if cost == 1 { "" } else { "s" }));
+            }
+        }
+        if let Some(kind) = slot {
+            logic::spend_slot(&mut self.character, kind)?;
+            spent.push(format!("1 {}", kind));
+        }
+
+        let mut entry = format!("Used {}", ability.name);
+        if !spent.is_empty() {
+            entry.push_str(&format!(" ({})", spent.join(", ")));
+        }
+
+        if let Some(expr) = dice::find_expression(&ability.body)
+            && let Ok(parsed) = dice::parse(&expr)
+        {
+            let mut result = parsed.roll(&mut self.rng);
+            result.label = Some(ability.name.clone());
+            entry.push_str(&format!(": rolled {} = {}", result.expression, result.total));
+            self.record_roll(result);
+        }
+
+        self.log_session(entry);
+        self.sync_inputs();
+        Ok(())
+    }
+
+    /// Names of the abilities picked for a loadout, or of the prepared ones
+    /// when no selection is in progress.
+    fn selected_ability_names(&self) -> Vec<String> {
+        self.character
+            .abilities
+            .iter()
+            .enumerate()
+            .filter(|(i, a)| match &self.loadout_selection {
+                Some(selection) => selection.contains(i),
+                None => a.prepared,
+            })
+            .map(|(_, a)| a.name.clone())
+            .collect()
+    }
+
+    /// Tells the user which loadout entries match no ability on the sheet,
+    /// e.g. after an ability was renamed or deleted.
+    fn missing_abilities_notice(missing: &[String]) -> Option<String> {
+        (!missing.is_empty()).then(|| {
+            format!(
+                "Not on this sheet, so left out of the loadout: {}.",
+                missing.join(", ")
+            )
+        })
+    }
+
+    fn clamp_hp_to_max(&mut self) {
+        let max = logic::calculate_max_hp(&self.character, &self.origins);
+        if self.character.current_hp > max {
+            self.character.current_hp = max;
+            self.hp_input = max.to_string();
+        }
+    }
+
+    fn advance_conditions(&mut self) {
+        let expired = logic::advance_conditions(&mut self.character);
+        if !expired.is_empty() {
+            self.log_session(format!("{} ended", expired.join(", ")));
+            self.sync_inputs();
+            self.clamp_hp_to_max();
+        }
+    }
+
+    fn log_session(&mut self, text: impl Into<String>) {
+        self.character.session_log.push(SessionLogEntry::new(text));
+    }
+
+    fn modifiers_mut(&mut self, owner: ModifierOwner) -> Option<&mut Vec<Modifier>> {
+        match owner {
+            ModifierOwner::Character => Some(&mut self.character.modifiers),
+            ModifierOwner::Item(i) => self.character.inventory.get_mut(i).map(|i| &mut i.modifiers),
+            ModifierOwner::Ability(i) => {
+                self.character.abilities.get_mut(i).map(|a| &mut a.modifiers)
+            }
+            ModifierOwner::Condition(i) => {
+                self.character.conditions.get_mut(i).map(|c| &mut c.modifiers)
+            }
+        }
+    }
+
+    fn modifier_mut(&mut self, owner: ModifierOwner, idx: usize) -> Option<&mut Modifier> {
+        self.modifiers_mut(owner)?.get_mut(idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Condition, ConditionDuration, Origin, StatTarget};
+
+    fn max_hp(sheet: &CharacterSheet) -> i32 {
+        logic::calculate_max_hp(&sheet.character, &sheet.origins)
+    }
+
+    /// A sheet with a condition granting `bonus` max HP and HP filled up to it.
+    fn boosted(bonus: i32, duration: ConditionDuration) -> CharacterSheet {
+        let mut condition = Condition::new("Vigour", duration);
+        condition
+            .modifiers
+            .push(Modifier::new("Vigour", StatTarget::MaxHp, bonus));
+        let mut sheet = CharacterSheet::default();
+        sheet.character.conditions.push(condition);
+        sheet.character.current_hp = max_hp(&sheet);
+        sheet
+    }
+
+    #[test]
+    fn test_ending_conditions_clamps_hp() {
+        let mut sheet = boosted(5, ConditionDuration::Long);
+        let _ = sheet.update(Message::ExpireCondition(0));
+        let max = max_hp(&sheet);
+        assert_eq!(sheet.character.current_hp, max);
+        assert_eq!(sheet.hp_input, max.to_string());
+
+        let mut sheet = boosted(5, ConditionDuration::Rounds(1));
+        let _ = sheet.update(Message::AdvanceConditions);
+        assert!(sheet.character.conditions.is_empty());
+        assert_eq!(sheet.character.current_hp, max_hp(&sheet));
+    }
+
+    #[test]
+    fn test_loadout_reports_missing_abilities() {
+        let mut sheet = CharacterSheet::default();
+        sheet.character.abilities.push(Ability {
+            name: "Parley".to_string(),
+            ..Ability::default()
+        });
+        sheet.character.loadouts.push(Loadout {
+            name: "Social".to_string(),
+            abilities: vec!["Parley".to_string(), "Sing".to_string()],
+        });
+
+        let _ = sheet.update(Message::LoadoutSelected("Social".to_string()));
+        assert_eq!(sheet.loadout_selection, Some(vec![0]));
+        assert!(sheet.prepare_error.as_deref().is_some_and(|e| e.contains("Sing")));
+
+        let _ = sheet.update(Message::PrepareSelection);
+        assert!(sheet.character.abilities[0].prepared);
+        assert_eq!(sheet.prepare_error, None);
+    }
+
+    #[test]
+    fn test_unprepared_ability_cannot_be_used() {
+        let mut sheet = CharacterSheet::default();
+        sheet.character.abilities.push(Ability {
+            name: "Parley".to_string(),
+            ..Ability::default()
+        });
+
+        let _ = sheet.update(Message::UseAbility(0));
+        assert_eq!(
+            sheet.ability_use_error,
+            Some((0, "Parley is not prepared.".to_string()))
+        );
+        assert!(sheet.character.session_log.is_empty());
+
+        sheet.character.abilities[0].prepared = true;
+        let _ = sheet.update(Message::UseAbility(0));
+        assert_eq!(sheet.ability_use_error, None);
+        assert_eq!(sheet.character.session_log.len(), 1);
+    }
+
+    #[test]
+    fn test_edits_mark_sheet_dirty_until_undone() {
+        let mut sheet = CharacterSheet::default();
+        assert!(!sheet.dirty);
+
+        let _ = sheet.update(Message::NameChanged("Aster".to_string()));
+        assert!(sheet.dirty);
+        assert!(sheet.title().starts_with('*'));
+
+        let _ = sheet.update(Message::Undo);
+        assert!(!sheet.dirty);
+        let _ = sheet.update(Message::Redo);
+        assert!(sheet.dirty);
+    }
+
+    #[test]
+    fn test_unsaved_changes_prompt_before_replacing() {
+        let mut sheet = CharacterSheet::default();
+        let _ = sheet.update(Message::StartCreation);
+        assert_eq!(sheet.unsaved_prompt, None);
+        assert!(sheet.creation.is_some());
+        let _ = sheet.update(Message::CancelCreation);
+
+        let _ = sheet.update(Message::NameChanged("Aster".to_string()));
+        let _ = sheet.update(Message::LoadCharacter);
+        assert_eq!(sheet.unsaved_prompt, Some(PendingAction::Load));
+        let _ = sheet.update(Message::CancelPendingAction);
+
+        let _ = sheet.update(Message::StartCreation);
+        assert_eq!(sheet.unsaved_prompt, Some(PendingAction::New));
+        assert!(sheet.creation.is_none());
+    }
+
+    #[test]
+    fn test_cancelled_save_dialog_drops_pending_action() {
+        let mut sheet = CharacterSheet::default();
+        let _ = sheet.update(Message::NameChanged("Aster".to_string()));
+        let _ = sheet.update(Message::StartCreation);
+
+        // With no file yet, saving first asks where to save.
+        let _ = sheet.update(Message::SaveBeforeContinuing);
+        assert_eq!(sheet.unsaved_prompt, None);
+        assert_eq!(sheet.after_save, Some(PendingAction::New));
+
+        let _ = sheet.update(Message::SaveFileSelected(None));
+        assert_eq!(sheet.after_save, None);
+        assert!(sheet.creation.is_none());
+        assert!(sheet.dirty);
+    }
+
+    #[test]
+    fn test_dr_qualifier_only_takes_a_damage_type() {
+        let mut sheet = CharacterSheet::default();
+        let _ = sheet.update(Message::DrQualifierChanged("/holy ".to_string()));
+        assert_eq!(sheet.character.dr_qualifier, "/holy");
+
+        let _ = sheet.update(Message::DrQualifierChanged("5/fire".to_string()));
+        assert_eq!(sheet.dr_qualifier_input, "5/fire");
+        assert_eq!(sheet.character.dr_qualifier, "/holy");
+
+        let _ = sheet.update(Message::DrQualifierChanged(String::new()));
+        assert_eq!(sheet.character.dr_qualifier, "");
+    }
+
+    #[test]
+    fn test_ui_only_messages_leave_character_alone() {
+        let mut sheet = CharacterSheet::default();
+        sheet.character.name = "Aster".to_string();
+        sheet.character.abilities.push(Ability {
+            name: "Parley".to_string(),
+            ..Ability::default()
+        });
+        sheet.character.inventory.push(Item::new("Rope"));
+        sheet
+            .character
+            .conditions
+            .push(Condition::new("Vigour", ConditionDuration::Long));
+        sheet.character.wounds.push(Wound::new("Cut", WoundHealing::Rest));
+        sheet.character.loadouts.push(Loadout {
+            name: "Social".to_string(),
+            abilities: vec!["Parley".to_string()],
+        });
+        sheet.loadout_selection = Some(Vec::new());
+        // A pending recovery keeps Autosave from touching the real recovery file.
+        sheet.recovery = Some(RecoverySnapshot::new(&Character::default(), None));
+        let before = sheet.character.clone();
 
-        while self.inventory_editors.len() < display_count {
-            let text = self
-                .character
-                .inventory
-                .get(self.inventory_editors.len())
-                .cloned()
-                .unwrap_or_default();
-            self.inventory_editors
-                .push(text_editor::Content::with_text(&text));
+        let messages = vec![
+            Message::ToggleEditor,
+            Message::ToggleSaveMenu,
+            Message::ToggleValidationReport,
+            Message::ToggleEditConditions,
+            Message::ToggleEditInventory,
+            Message::ToggleEditAbilities,
+            Message::ToggleAbilityBrowser,
+            Message::AbilityBrowserSearchChanged("par".to_string()),
+            Message::AbilityBrowserTagToggled("Social".to_string()),
+            Message::WoundInputChanged("Bruise".to_string()),
+            Message::WoundHealingSelected(WoundHealing::Treatment),
+            Message::DamageInputChanged("3".to_string()),
+            Message::DamageNoteChanged("Goblin".to_string()),
+            Message::BuyNameChanged("Lantern".to_string()),
+            Message::BuyPriceChanged("5".to_string()),
+            Message::BuyKindSelected(PurchaseKind::Weapon),
+            Message::LedgerAmountChanged("10".to_string()),
+            Message::LedgerReasonChanged("Reward".to_string()),
+            Message::XpInputChanged("100".to_string()),
+            Message::LoadoutNameChanged("Combat".to_string()),
+            Message::DiceExpressionChanged("2d6".to_string()),
+            Message::LevelUpAttributeChanged(AttributeField::Strength, true),
+            Message::CancelLevelUp,
+            Message::BeginCreation,
+            Message::CreationNameChanged("Bryn".to_string()),
+            Message::CreationOriginSelected(Origin::new("Dwarf")),
+            Message::CreationAttributeChanged(AttributeField::Luck, 3),
+            Message::CreationBudgetChanged("12".to_string()),
+            Message::CreationTenderChanged("20".to_string()),
+            Message::CreationItemInputChanged("Torch".to_string()),
+            Message::CreationAddItem,
+            Message::CreationRemoveItem(0),
+            Message::CreationAbilityToggled(sheet.character.abilities[0].clone(), true),
+            Message::CreationNext,
+            Message::CreationBack,
+            Message::CancelCreation,
+            Message::RequestDeleteAbility(0),
+            Message::CancelDeleteAbility,
+            Message::LoadoutSelectionToggled(0, true),
+            Message::CancelLoadoutSelection,
+            Message::RequestClearRollLog,
+            Message::CancelClearRollLog,
+            Message::ShowVersionHistory,
+            Message::VersionSelected(0),
+            Message::CloseVersionHistory,
+            Message::CancelPendingAction,
+            Message::DismissError,
+            Message::DismissNotification,
+            Message::DismissSalvageReport,
+            Message::Autosave,
+        ];
+        for message in messages {
+            assert!(message.is_ui_only(), "{:?} is not UI-only", message);
+            let _ = sheet.update(message.clone());
+            assert_eq!(sheet.character, before, "{:?} changed the character", message);
         }
+        assert!(!sheet.history.can_undo());
     }
 }
@@ -0,0 +1,210 @@
+use crate::model::{self, Character};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Number of earlier versions kept for each character file.
+pub const BACKUP_LIMIT: usize = 10;
+
+/// An earlier version of a character file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// Unix seconds when this version was replaced.
+    pub saved_at: u64,
+}
+
+impl Backup {
+    pub fn load(&self) -> Result<Character, String> {
+        let content = fs::read_to_string(&self.path).map_err(|e| e.to_string())?;
+        model::character_from_json(&content)
+    }
+}
+
+/// Writes through a temporary file in the same folder and renames it over
+/// `path`, so a crash mid-write never leaves a half-written file behind.
+pub fn write_atomic(path: &Path, contents: &str) -> Result<(), String> {
+    let file_name = path.file_name().ok_or("Invalid file path")?;
+    let temp = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+    let result = fs::File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result.map_err(|e| e.to_string())
+}
+
+/// Folder holding the earlier versions of `path`: `backups/<name>` next to it.
+pub fn backup_dir(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default();
+    path.with_file_name("backups").join(stem)
+}
+
+/// Saves `contents` to `path`, first copying the current version into the
+/// backups folder and dropping the oldest versions past [`BACKUP_LIMIT`].
+/// No backup is made when the file already holds `contents`.
+pub fn save_with_backup(path: &Path, contents: &str, now: u64) -> Result<(), String> {
+    let unchanged = fs::read_to_string(path).is_ok_and(|current| current == contents);
+    if path.exists() && !unchanged {
+        let dir = backup_dir(path);
+        fs::create_dir_all(&dir).map_err(|e| format!("Could not create backups folder: {}", e))?;
+        // Saves within the same second get `<now>-1.json`, `<now>-2.json`, ...
+        let mut backup = dir.join(format!("{}.json", now));
+        let mut n = 1;
+        while backup.exists() {
+            backup = dir.join(format!("{}-{}.json", now, n));
+            n += 1;
+        }
+        fs::copy(path, backup)
+            .map_err(|e| format!("Could not back up the previous version: {}", e))?;
+        for old in list_backups(path).into_iter().skip(BACKUP_LIMIT) {
+            let _ = fs::remove_file(old.path);
+        }
+    }
+    write_atomic(path, contents)
+}
+
+/// Earlier versions of `path`, newest first.
+pub fn list_backups(path: &Path) -> Vec<Backup> {
+    let Ok(entries) = fs::read_dir(backup_dir(path)) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<(u32, Backup)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            let stem = path.file_stem()?.to_str()?;
+            let (saved_at, seq) = stem.split_once('-').unwrap_or((stem, "0"));
+            let saved_at = saved_at.parse().ok()?;
+            Some((seq.parse().ok()?, Backup { path, saved_at }))
+        })
+        .collect();
+    backups.sort_by_key(|(seq, b)| std::cmp::Reverse((b.saved_at, *seq)));
+    backups.into_iter().map(|(_, b)| b).collect()
+}
+
+/// Lists the fields that differ between two versions of a character, one
+/// line each, e.g. `attributes.strength: 2 → 3` or `+ inventory[4].name: Rope`.
+pub fn diff(old: &Character, new: &Character) -> Vec<String> {
+    let old = flatten(old);
+    let new = flatten(new);
+    let mut lines = Vec::new();
+    for (key, old_value) in &old {
+        match new.get(key) {
+            Some(new_value) if new_value != old_value => {
+                lines.push(format!("{}: {} → {}", key, old_value, new_value))
+            }
+            Some(_) => {}
+            None => lines.push(format!("- {}: {}", key, old_value)),
+        }
+    }
+    for (key, new_value) in &new {
+        if !old.contains_key(key) {
+            lines.push(format!("+ {}: {}", key, new_value));
+        }
+    }
+    lines
+}
+
+fn flatten(char: &Character) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    if let Ok(value) = serde_json::to_value(char) {
+        flatten_value(String::new(), &value, &mut fields);
+    }
+    fields
+}
+
+fn flatten_value(prefix: String, value: &Value, fields: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_value(path, value, fields);
+            }
+        }
+        Value::Array(items) => {
+            for (i, value) in items.iter().enumerate() {
+                flatten_value(format!("{}[{}]", prefix, i), value, fields);
+            }
+        }
+        Value::String(s) => {
+            fields.insert(prefix, s.clone());
+        }
+        _ => {
+            fields.insert(prefix, value.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Item;
+
+    #[test]
+    fn test_backups_rotate() {
+        let dir = std::env::temp_dir().join(format!("bastion_backup_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let sheet = dir.join("Aster.json");
+
+        for i in 0..(BACKUP_LIMIT as u64 + 3) {
+            save_with_backup(&sheet, &i.to_string(), i).unwrap();
+        }
+        assert_eq!(fs::read_to_string(&sheet).unwrap(), "12");
+        assert!(!dir.join(".Aster.json.tmp").exists());
+
+        let backups = list_backups(&sheet);
+        assert_eq!(backups.len(), BACKUP_LIMIT);
+        assert_eq!(backups[0].saved_at, 12);
+        assert_eq!(fs::read_to_string(&backups[0].path).unwrap(), "11");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_backups_within_one_second() {
+        let dir = std::env::temp_dir().join(format!("bastion_backup_same_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let sheet = dir.join("Aster.json");
+
+        for contents in ["a", "b", "c", "c"] {
+            save_with_backup(&sheet, contents, 5).unwrap();
+        }
+        // Saving "c" again changed nothing, so it left no backup.
+        let backups = list_backups(&sheet);
+        let contents: Vec<String> = backups
+            .iter()
+            .map(|b| fs::read_to_string(&b.path).unwrap())
+            .collect();
+        assert_eq!(contents, vec!["b", "a"]);
+        assert!(backups.iter().all(|b| b.saved_at == 5));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = Character::default();
+        let mut new = old.clone();
+        new.name = "Astra".to_string();
+        new.inventory.push(Item::new("Rope"));
+
+        let lines = diff(&old, &new);
+        assert!(lines.contains(&format!("name: {} → Astra", old.name)));
+        assert!(lines.contains(&"+ inventory[0].name: Rope".to_string()));
+        assert!(diff(&old, &old).is_empty());
+    }
+}
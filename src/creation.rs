@@ -0,0 +1,317 @@
+use crate::logic;
+use crate::message::AttributeField;
+use crate::model::{Ability, Attributes, Character, Item, LedgerEntry, Origin};
+use crate::origins::{self, OriginDef, OriginRules};
+
+pub const DEFAULT_POINT_BUDGET: i32 = 8;
+pub const DEFAULT_STARTING_TENDER: i32 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreationStep {
+    Identity,
+    Attributes,
+    Equipment,
+    Abilities,
+    Review,
+}
+
+impl CreationStep {
+    pub fn all() -> [CreationStep; 5] {
+        [
+            CreationStep::Identity,
+            CreationStep::Attributes,
+            CreationStep::Equipment,
+            CreationStep::Abilities,
+            CreationStep::Review,
+        ]
+    }
+
+    pub fn index(self) -> usize {
+        Self::all().iter().position(|s| *s == self).unwrap_or(0)
+    }
+
+    pub fn next(self) -> Option<CreationStep> {
+        Self::all().get(self.index() + 1).copied()
+    }
+
+    pub fn previous(self) -> Option<CreationStep> {
+        self.index().checked_sub(1).map(|i| Self::all()[i])
+    }
+}
+
+impl std::fmt::Display for CreationStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                CreationStep::Identity => "Name & Origin",
+                CreationStep::Attributes => "Attributes",
+                CreationStep::Equipment => "Tender & Equipment",
+                CreationStep::Abilities => "Abilities",
+                CreationStep::Review => "Review",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CreationDraft {
+    pub step: CreationStep,
+    pub name: String,
+    pub origin: Origin,
+    /// Rules of `origin`, looked up when it is chosen.
+    pub rules: OriginRules,
+    pub attributes: Attributes,
+    pub point_budget: i32,
+    pub starting_tender: i32,
+    pub items: Vec<String>,
+    pub abilities: Vec<Ability>,
+}
+
+impl CreationDraft {
+    pub fn new(defs: &[OriginDef]) -> Self {
+        let origin = Origin::default();
+        let mut draft = Self {
+            step: CreationStep::Identity,
+            name: String::new(),
+            rules: origins::rules(defs, &origin).clone(),
+            origin,
+            attributes: Attributes::default(),
+            point_budget: DEFAULT_POINT_BUDGET,
+            starting_tender: DEFAULT_STARTING_TENDER,
+            items: Vec::new(),
+            abilities: Vec::new(),
+        };
+        draft.apply_origin_rules();
+        draft
+    }
+
+    /// Free starting value of an attribute before any points are spent.
+    pub fn base_attribute(&self, field: AttributeField) -> i32 {
+        self.rules.starting_attribute(field)
+    }
+
+    pub fn attribute(&self, field: AttributeField) -> i32 {
+        self.attributes.get(field)
+    }
+
+    pub fn set_attribute(&mut self, field: AttributeField, value: i32) {
+        let min = self.base_attribute(field);
+        *self.attributes.get_mut(field) = value.clamp(min, logic::max_attribute_value(1));
+    }
+
+    pub fn set_origin(&mut self, defs: &[OriginDef], origin: Origin) {
+        self.rules = origins::rules(defs, &origin).clone();
+        self.origin = origin;
+        self.apply_origin_rules();
+    }
+
+    /// Raises any attribute below the origin's free starting value.
+    fn apply_origin_rules(&mut self) {
+        for field in AttributeField::all() {
+            let value = self.attribute(field);
+            self.set_attribute(field, value);
+        }
+    }
+
+    pub fn points_spent(&self) -> i32 {
+        AttributeField::all()
+            .iter()
+            .map(|f| self.attribute(*f) - self.base_attribute(*f))
+            .sum()
+    }
+
+    pub fn points_remaining(&self) -> i32 {
+        self.point_budget - self.points_spent()
+    }
+
+    pub fn has_ability(&self, name: &str) -> bool {
+        self.abilities.iter().any(|a| a.name == name)
+    }
+
+    pub fn build(&self, defs: &[OriginDef]) -> Character {
+        let mut char = self.character();
+        char.current_hp = logic::calculate_max_hp(&char, defs);
+        char
+    }
+
+    /// The built character before its HP are filled up.
+    fn character(&self) -> Character {
+        let mut char = Character {
+            name: self.name.trim().to_string(),
+            origin: self.origin.clone(),
+            attributes: self.attributes.clone(),
+            ledger: Vec::new(),
+            ..Character::default()
+        };
+        if self.starting_tender != 0 {
+            char.ledger.push(LedgerEntry::new(
+                self.starting_tender,
+                "Starting tender",
+                None,
+            ));
+        }
+        char.inventory = self
+            .items
+            .iter()
+            .map(|name| Item::new(name.trim()))
+            .collect();
+        char.abilities = self
+            .abilities
+            .iter()
+            .cloned()
+            .map(|mut a| {
+                a.prepared = true;
+                a
+            })
+            .collect();
+        char
+    }
+
+    /// Problems that prevent moving on from `step`.
+    pub fn validate_step(&self, step: CreationStep) -> Vec<String> {
+        let mut errors = Vec::new();
+        match step {
+            CreationStep::Identity => {
+                if self.name.trim().is_empty() {
+                    errors.push("Enter a name for the character.".to_string());
+                }
+            }
+            CreationStep::Attributes => {
+                if self.point_budget < 0 {
+                    errors.push("The point budget cannot be negative.".to_string());
+                }
+                let remaining = self.points_remaining();
+                if remaining < 0 {
+                    errors.push(format!(
+                        "{} points over the budget of {}.",
+                        -remaining, self.point_budget
+                    ));
+                }
+                let max = logic::max_attribute_value(1);
+                for field in AttributeField::all() {
+                    let value = self.attribute(field);
+                    let min = self.base_attribute(field);
+                    if value < min || value > max {
+                        errors.push(format!("{} must be between {} and {}.", field, min, max));
+                    }
+                }
+            }
+            CreationStep::Equipment => {
+                if self.starting_tender < 0 {
+                    errors.push("Starting tender cannot be negative.".to_string());
+                }
+                if self.items.iter().any(|i| i.trim().is_empty()) {
+                    errors.push("Starting items need a name.".to_string());
+                }
+            }
+            CreationStep::Abilities => {
+                let slots = logic::calculate_prepared_slots(&self.character());
+                if self.abilities.len() as i32 > slots {
+                    errors.push(format!(
+                        "Pick at most {} abilities ({} picked).",
+                        slots,
+                        self.abilities.len()
+                    ));
+                }
+            }
+            CreationStep::Review => {
+                for step in CreationStep::all() {
+                    if step != CreationStep::Review {
+                        errors.extend(self.validate_step(step));
+                    }
+                }
+            }
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_human_starting_luck() {
+        let defs = origins::builtin_origins();
+        let mut draft = CreationDraft::new(&defs);
+        assert_eq!(draft.attribute(AttributeField::Luck), 3);
+        assert_eq!(draft.points_spent(), 0);
+
+        draft.set_origin(&defs, Origin::new("Elf"));
+        assert_eq!(draft.attribute(AttributeField::Luck), 3);
+        assert_eq!(draft.points_spent(), 2);
+
+        draft.set_attribute(AttributeField::Luck, 1);
+        draft.set_origin(&defs, Origin::default());
+        assert_eq!(draft.attribute(AttributeField::Luck), 3);
+        assert_eq!(draft.points_spent(), 0);
+    }
+
+    #[test]
+    fn test_point_budget() {
+        let mut draft = CreationDraft {
+            name: "Aster".to_string(),
+            point_budget: 4,
+            ..CreationDraft::new(&origins::builtin_origins())
+        };
+        draft.set_attribute(AttributeField::Strength, 4);
+        draft.set_attribute(AttributeField::Dexterity, 2);
+        assert_eq!(draft.points_remaining(), 0);
+        assert!(draft.validate_step(CreationStep::Attributes).is_empty());
+
+        draft.set_attribute(AttributeField::Will, 2);
+        assert_eq!(draft.validate_step(CreationStep::Attributes).len(), 1);
+
+        draft.set_attribute(AttributeField::Faith, 9);
+        assert_eq!(draft.attribute(AttributeField::Faith), 4);
+    }
+
+    #[test]
+    fn test_step_validation() {
+        let mut draft = CreationDraft::new(&origins::builtin_origins());
+        assert_eq!(draft.validate_step(CreationStep::Identity).len(), 1);
+
+        draft.name = "Aster".to_string();
+        draft.items = vec!["Rope".to_string(), " ".to_string()];
+        assert_eq!(draft.validate_step(CreationStep::Equipment).len(), 1);
+
+        draft.items.pop();
+        draft.abilities = (0..4)
+            .map(|i| Ability {
+                name: format!("Ability {}", i),
+                ..Ability::default()
+            })
+            .collect();
+        assert_eq!(draft.validate_step(CreationStep::Abilities).len(), 1);
+        assert_eq!(draft.validate_step(CreationStep::Review).len(), 1);
+
+        draft.abilities.pop();
+        assert!(draft.validate_step(CreationStep::Review).is_empty());
+    }
+
+    #[test]
+    fn test_build_character() {
+        let defs = origins::builtin_origins();
+        let mut draft = CreationDraft {
+            name: " Aster ".to_string(),
+            starting_tender: 150,
+            items: vec!["Rope".to_string()],
+            ..CreationDraft::new(&defs)
+        };
+        draft.set_attribute(AttributeField::Endurance, 3);
+        draft.abilities.push(Ability {
+            name: "Second Wind".to_string(),
+            ..Ability::default()
+        });
+
+        let char = draft.build(&defs);
+        assert_eq!(char.name, "Aster");
+        assert_eq!(char.attributes.luck, 3);
+        assert_eq!(logic::calculate_tender(&char), 150);
+        assert_eq!(char.inventory[0].name, "Rope");
+        assert!(char.abilities[0].prepared);
+        assert_eq!(char.current_hp, logic::calculate_max_hp(&char, &defs));
+    }
+}
@@ -0,0 +1,475 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+const MAX_DICE: u32 = 100;
+const MAX_SIDES: u32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keep {
+    Highest(u32),
+    Lowest(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    Dice {
+        sign: i32,
+        count: u32,
+        sides: u32,
+        keep: Option<Keep>,
+    },
+    Flat(i32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiceExpr {
+    pub terms: Vec<Term>,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DieRoll {
+    pub sides: u32,
+    pub value: u32,
+    pub sign: i32,
+    pub kept: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RollResult {
+    pub expression: String,
+    pub label: Option<String>,
+    pub dice: Vec<DieRoll>,
+    pub modifier: i32,
+    pub total: i32,
+    pub crit: bool,
+}
+
+impl std::fmt::Display for DiceExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, term) in self.terms.iter().enumerate() {
+            let (sign, body) = match term {
+                Term::Dice {
+                    sign,
+                    count,
+                    sides,
+                    keep,
+                } => {
+                    let keep = match keep {
+                        Some(Keep::Highest(n)) => format!("kh{}", n),
+                        Some(Keep::Lowest(n)) => format!("kl{}", n),
+                        None => String::new(),
+                    };
+                    (*sign, format!("{}d{}{}", count, sides, keep))
+                }
+                Term::Flat(n) => (n.signum(), n.abs().to_string()),
+            };
+            if sign < 0 {
+                write!(f, "-")?;
+            } else if i > 0 {
+                write!(f, "+")?;
+            }
+            write!(f, "{}", body)?;
+        }
+        if let Some(label) = &self.label {
+            write!(f, " {}", label)?;
+        }
+        Ok(())
+    }
+}
+
+fn take_number(input: &str) -> Result<(Option<u32>, &str), String> {
+    let end = input
+        .char_indices()
+        .find(|(_, c)| !c.is_ascii_digit())
+        .map(|(i, _)| i)
+        .unwrap_or(input.len());
+    if end == 0 {
+        return Ok((None, input));
+    }
+    let digits = &input[..end];
+    let n = digits
+        .parse()
+        .map_err(|_| format!("Number {} is too large", digits))?;
+    Ok((Some(n), &input[end..]))
+}
+
+fn parse_term(input: &str, sign: i32) -> Result<(Term, &str), String> {
+    let (count, rest) = take_number(input)?;
+
+    let Some(rest) = rest.strip_prefix(['d', 'D']) else {
+        return match count {
+            Some(n) => {
+                let n = i32::try_from(n).map_err(|_| format!("Number {} is too large", n))?;
+                Ok((Term::Flat(sign * n), rest))
+            }
+            None => Err(format!("Expected a number or dice at '{}'", input)),
+        };
+    };
+
+    let count = count.unwrap_or(1);
+    let (sides, rest) = take_number(rest)?;
+    let sides = sides.ok_or_else(|| "Expected number of sides after 'd'".to_string())?;
+
+    if count == 0 || count > MAX_DICE {
+        return Err(format!("Dice count must be between 1 and {}", MAX_DICE));
+    }
+    if sides == 0 || sides > MAX_SIDES {
+        return Err(format!("Dice sides must be between 1 and {}", MAX_SIDES));
+    }
+
+    let lower = rest.to_ascii_lowercase();
+    let (keep, rest) = if lower.starts_with("kh") || lower.starts_with("kl") {
+        let (n, after) = take_number(&rest[2..])?;
+        let n = n.unwrap_or(1);
+        if n == 0 || n > count {
+            return Err(format!("Cannot keep {} of {} dice", n, count));
+        }
+        let keep = if lower.starts_with("kh") {
+            Keep::Highest(n)
+        } else {
+            Keep::Lowest(n)
+        };
+        (Some(keep), after)
+    } else {
+        (None, rest)
+    };
+
+    Ok((
+        Term::Dice {
+            sign,
+            count,
+            sides,
+            keep,
+        },
+        rest,
+    ))
+}
+
+/// Parses expressions such as `2d6+3`, `3d6kh2` or `+1d6 Circumstance`.
+/// Any trailing words after the last term are kept as the roll's label.
+pub fn parse(input: &str) -> Result<DiceExpr, String> {
+    let mut terms = Vec::new();
+    let mut rest = input.trim();
+
+    if rest.is_empty() {
+        return Err("Dice expression is empty".to_string());
+    }
+
+    loop {
+        rest = rest.trim_start();
+        let sign = if let Some(r) = rest.strip_prefix('+') {
+            rest = r;
+            1
+        } else if let Some(r) = rest.strip_prefix('-') {
+            rest = r;
+            -1
+        } else if terms.is_empty() {
+            1
+        } else {
+            break;
+        };
+
+        let (term, r) = parse_term(rest.trim_start(), sign)?;
+        terms.push(term);
+        rest = r;
+    }
+
+    let label = rest.trim();
+    let label = if label.is_empty() {
+        None
+    } else if label.starts_with(|c: char| c.is_alphabetic()) {
+        Some(label.to_string())
+    } else {
+        return Err(format!("Unexpected '{}' in dice expression", label));
+    };
+
+    let expr = DiceExpr { terms, label };
+    if expr.max_magnitude().is_none() {
+        return Err("Dice expression total is too large".to_string());
+    }
+    Ok(expr)
+}
+
+impl DiceExpr {
+    /// Largest total the expression could reach in either direction, or
+    /// `None` when that does not fit in an `i32`. Parsed expressions always
+    /// fit, so rolling them cannot overflow.
+    fn max_magnitude(&self) -> Option<i32> {
+        self.terms.iter().try_fold(0i32, |acc, term| {
+            let term_max = match *term {
+                Term::Dice { count, sides, .. } => i32::try_from(count.checked_mul(sides)?).ok()?,
+                Term::Flat(n) => n.checked_abs()?,
+            };
+            acc.checked_add(term_max)
+        })
+    }
+
+    pub fn roll<R: Rng + ?Sized>(&self, rng: &mut R) -> RollResult {
+        let mut dice = Vec::new();
+        let mut modifier = 0i32;
+        let mut total = 0i32;
+
+        for term in &self.terms {
+            match *term {
+                Term::Dice {
+                    sign,
+                    count,
+                    sides,
+                    keep,
+                } => {
+                    let mut rolled: Vec<DieRoll> = (0..count)
+                        .map(|_| DieRoll {
+                            sides,
+                            value: rng.random_range(1..=sides),
+                            sign,
+                            kept: true,
+                        })
+                        .collect();
+
+                    if let Some(keep) = keep {
+                        let mut order: Vec<usize> = (0..rolled.len()).collect();
+                        let kept = match keep {
+                            Keep::Highest(n) => {
+                                order.sort_by(|a, b| rolled[*b].value.cmp(&rolled[*a].value));
+                                n
+                            }
+                            Keep::Lowest(n) => {
+                                order.sort_by(|a, b| rolled[*a].value.cmp(&rolled[*b].value));
+                                n
+                            }
+                        };
+                        for idx in order.into_iter().skip(kept as usize) {
+                            rolled[idx].kept = false;
+                        }
+                    }
+
+                    // Saturate rather than wrap for expressions built by hand;
+                    // parsed ones are bounded by `max_magnitude`.
+                    let kept = rolled
+                        .iter()
+                        .filter(|d| d.kept)
+                        .fold(0i32, |sum, d| sum.saturating_add(d.value as i32));
+                    total = total.saturating_add(sign.saturating_mul(kept));
+                    dice.extend(rolled);
+                }
+                Term::Flat(n) => {
+                    modifier = modifier.saturating_add(n);
+                    total = total.saturating_add(n);
+                }
+            }
+        }
+
+        let mut expression = self.clone();
+        expression.label = None;
+
+        RollResult {
+            expression: expression.to_string(),
+            label: self.label.clone(),
+            dice,
+            modifier,
+            total,
+            crit: false,
+        }
+    }
+}
+
+/// Rolls a d12 check with a flat bonus. The roll is flagged as critical when
+/// the d12 lands at or above `crit_range`.
+pub fn roll_check<R: Rng + ?Sized>(
+    rng: &mut R,
+    bonus: i32,
+    crit_range: i32,
+    label: &str,
+) -> RollResult {
+    let expr = DiceExpr {
+        terms: vec![
+            Term::Dice {
+                sign: 1,
+                count: 1,
+                sides: 12,
+                keep: None,
+            },
+            Term::Flat(bonus),
+        ],
+        label: Some(label.to_string()),
+    };
+    let mut result = expr.roll(rng);
+    result.crit = result.dice.first().is_some_and(|d| d.value as i32 >= crit_range);
+    result
+}
+
+/// Finds the first dice expression in free text, such as the `2d6+1` in
+/// "deal 2d6+1 damage". Flat numbers on their own are ignored.
+pub fn find_expression(text: &str) -> Option<String> {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_ascii_alphanumeric()))
+        .find(|word| {
+            parse(word).is_ok_and(|expr| {
+                expr.label.is_none() && expr.terms.iter().any(|t| matches!(t, Term::Dice { .. }))
+            })
+        })
+        .map(str::to_string)
+}
+
+pub fn roll(input: &str, rng: &mut impl Rng) -> Result<RollResult, String> {
+    Ok(parse(input)?.roll(rng))
+}
+
+impl RollResult {
+    /// One-line description, e.g. `2d6+3: [4, 5] + 3 = 12`. Dropped dice are
+    /// prefixed with `~`.
+    pub fn summary(&self) -> String {
+        let dice = self
+            .dice
+            .iter()
+            .map(|d| {
+                let sign = if d.sign < 0 { "-" } else { "" };
+                if d.kept {
+                    format!("{}{}", sign, d.value)
+                } else {
+                    format!("~{}{}", sign, d.value)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut line = format!("{}: [{}]", self.expression, dice);
+        if self.modifier > 0 {
+            line.push_str(&format!(" + {}", self.modifier));
+        } else if self.modifier < 0 {
+            line.push_str(&format!(" - {}", -self.modifier));
+        }
+        line.push_str(&format!(" = {}", self.total));
+        if let Some(label) = &self.label {
+            line.push_str(&format!(" {}", label));
+        }
+        if self.crit {
+            line.push_str(" (Critical!)");
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_parse_expressions() {
+        let expr = parse("2d6+3").unwrap();
+        assert_eq!(
+            expr.terms,
+            vec![
+                Term::Dice {
+                    sign: 1,
+                    count: 2,
+                    sides: 6,
+                    keep: None
+                },
+                Term::Flat(3)
+            ]
+        );
+        assert_eq!(expr.label, None);
+
+        let expr = parse("d12").unwrap();
+        assert_eq!(expr.to_string(), "1d12");
+
+        let expr = parse("3d6kh2").unwrap();
+        assert_eq!(
+            expr.terms[0],
+            Term::Dice {
+                sign: 1,
+                count: 3,
+                sides: 6,
+                keep: Some(Keep::Highest(2))
+            }
+        );
+
+        let expr = parse("+1d6 Circumstance").unwrap();
+        assert_eq!(expr.label.as_deref(), Some("Circumstance"));
+        assert_eq!(expr.to_string(), "1d6 Circumstance");
+
+        let expr = parse("1d20 - 2").unwrap();
+        assert_eq!(expr.terms[1], Term::Flat(-2));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("").is_err());
+        assert!(parse("d").is_err());
+        assert!(parse("0d6").is_err());
+        assert!(parse("2d6kh3").is_err());
+        assert!(parse("2d6 3").is_err());
+        assert!(parse("1000d6").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_overflow() {
+        assert!(parse("99999999999d6").is_err());
+        assert!(parse("1d99999999999").is_err());
+        assert!(parse("4d6kh99999999999").is_err());
+        assert!(parse("+3000000000").is_err());
+        assert!(parse("2147483647+1").is_err());
+        assert!(parse("1d6-2147483647").is_err());
+
+        let expr = parse("2147483647").unwrap();
+        assert_eq!(expr.roll(&mut StdRng::seed_from_u64(1)).total, i32::MAX);
+        assert_eq!(parse("-2147483647").unwrap().terms, vec![Term::Flat(-i32::MAX)]);
+    }
+
+    #[test]
+    fn test_roll_is_seedable() {
+        let expr = parse("4d6kh3+1").unwrap();
+        let a = expr.roll(&mut StdRng::seed_from_u64(7));
+        let b = expr.roll(&mut StdRng::seed_from_u64(7));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_roll_totals() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let result = roll("3d6kh2+2", &mut rng).unwrap();
+            assert_eq!(result.dice.len(), 3);
+            assert_eq!(result.dice.iter().filter(|d| d.kept).count(), 2);
+            let kept: i32 = result
+                .dice
+                .iter()
+                .filter(|d| d.kept)
+                .map(|d| d.value as i32)
+                .sum();
+            let dropped = result.dice.iter().find(|d| !d.kept).unwrap();
+            assert!(result.dice.iter().filter(|d| d.kept).all(|d| d.value >= dropped.value));
+            assert_eq!(result.total, kept + 2);
+            assert!((4..=14).contains(&result.total));
+        }
+    }
+
+    #[test]
+    fn test_find_expression() {
+        assert_eq!(
+            find_expression("Deal 2d6+1 damage to a target.").as_deref(),
+            Some("2d6+1")
+        );
+        assert_eq!(find_expression("Heal (1d8) HP").as_deref(), Some("1d8"));
+        assert_eq!(find_expression("Move 3 spaces and draw a card"), None);
+    }
+
+    #[test]
+    fn test_roll_check_crit() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..200 {
+            let result = roll_check(&mut rng, 2, 10, "Strength check");
+            let d12 = result.dice[0].value as i32;
+            assert_eq!(result.total, d12 + 2);
+            assert_eq!(result.crit, d12 >= 10);
+            assert_eq!(result.label.as_deref(), Some("Strength check"));
+        }
+
+        let result = roll_check(&mut rng, 0, 1, "Luck check");
+        assert!(result.crit);
+    }
+}
@@ -0,0 +1,216 @@
+use crate::model::Ability;
+use serde::{Deserialize, Serialize};
+
+pub const ACTIONS_PER_TURN: i32 = 3;
+/// Reactions available each round, spent by abilities tagged "Punish".
+pub const REACTIONS_PER_ROUND: i32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DamageEntry {
+    pub round: u32,
+    /// Damage dealt by the character rather than taken.
+    pub dealt: bool,
+    pub amount: i32,
+    /// Damage before damage reduction; equal to `amount` for damage dealt.
+    pub raw: i32,
+    pub note: String,
+}
+
+/// Turn structure for a fight: the round counter, this turn's action and
+/// reaction budget, and a log of damage taken and dealt.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Encounter {
+    pub round: u32,
+    pub actions_remaining: i32,
+    pub reactions_remaining: i32,
+    /// Names of the Punish abilities used this round.
+    #[serde(default)]
+    pub punishes_used: Vec<String>,
+    #[serde(default)]
+    pub damage_log: Vec<DamageEntry>,
+}
+
+impl Default for Encounter {
+    fn default() -> Self {
+        Self {
+            round: 1,
+            actions_remaining: ACTIONS_PER_TURN,
+            reactions_remaining: REACTIONS_PER_ROUND,
+            punishes_used: Vec::new(),
+            damage_log: Vec::new(),
+        }
+    }
+}
+
+/// Actions an ability costs, read from its "1 Action", "2 Actions" or
+/// "3 Actions" tag.
+pub fn action_cost(ability: &Ability) -> Option<i32> {
+    (1..=ACTIONS_PER_TURN).find(|n| {
+        ability.has_tag(&format!("{} Action", n)) || ability.has_tag(&format!("{} Actions", n))
+    })
+}
+
+pub fn is_punish(ability: &Ability) -> bool {
+    ability.has_tag("Punish")
+}
+
+impl Encounter {
+    /// Starts the next round, refreshing actions and reactions.
+    pub fn next_round(&mut self) {
+        self.round += 1;
+        self.actions_remaining = ACTIONS_PER_TURN;
+        self.reactions_remaining = REACTIONS_PER_ROUND;
+        self.punishes_used.clear();
+    }
+
+    pub fn punish_available(&self, ability: &Ability) -> bool {
+        self.reactions_remaining > 0 && !self.punishes_used.contains(&ability.name)
+    }
+
+    /// Checks the ability can be used this turn without spending anything.
+    pub fn can_use(&self, ability: &Ability) -> Result<(), String> {
+        if is_punish(ability) {
+            if !self.punish_available(ability) {
+                return Err(format!("No reaction left for {} this round.", ability.name));
+            }
+        } else if let Some(cost) = action_cost(ability)
+            && cost > self.actions_remaining
+        {
+            return Err(format!(
+                "{} needs {} actions but only {} remain.",
+                ability.name, cost, self.actions_remaining
+            ));
+        }
+        Ok(())
+    }
+
+    /// Spends the actions or reaction an ability needs.
+    pub fn use_ability(&mut self, ability: &Ability) -> Result<(), String> {
+        self.can_use(ability)?;
+        if is_punish(ability) {
+            self.reactions_remaining -= 1;
+            self.punishes_used.push(ability.name.clone());
+        } else if let Some(cost) = action_cost(ability) {
+            self.actions_remaining -= cost;
+        }
+        Ok(())
+    }
+
+    /// Records damage taken after `dr` is subtracted, returning the damage
+    /// that gets through.
+    pub fn take_damage(&mut self, raw: i32, dr: i32, note: &str) -> i32 {
+        let amount = (raw - dr).max(0);
+        self.damage_log.push(DamageEntry {
+            round: self.round,
+            dealt: false,
+            amount,
+            raw,
+            note: note.trim().to_string(),
+        });
+        amount
+    }
+
+    pub fn deal_damage(&mut self, amount: i32, note: &str) {
+        self.damage_log.push(DamageEntry {
+            round: self.round,
+            dealt: true,
+            amount,
+            raw: amount,
+            note: note.trim().to_string(),
+        });
+    }
+
+    pub fn damage_taken(&self) -> i32 {
+        self.damage_log
+            .iter()
+            .filter(|e| !e.dealt)
+            .map(|e| e.amount)
+            .sum()
+    }
+
+    pub fn damage_dealt(&self) -> i32 {
+        self.damage_log
+            .iter()
+            .filter(|e| e.dealt)
+            .map(|e| e.amount)
+            .sum()
+    }
+
+    /// One-line wrap-up for the session log.
+    pub fn summary(&self) -> String {
+        format!(
+            "Encounter ended after {} round{}: took {} damage, dealt {}",
+            self.round,
+            if self.round == 1 { "" } else { "s" },
+            self.damage_taken(),
+            self.damage_dealt()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ability(name: &str, tags: &str) -> Ability {
+        Ability {
+            name: name.to_string(),
+            tags: tags.to_string(),
+            ..Ability::default()
+        }
+    }
+
+    #[test]
+    fn test_action_budget() {
+        let mut encounter = Encounter::default();
+        let strike = ability("Strike", "1 Action, Maneuver");
+        let fireball = ability("Fireball", "Spell, 2 Actions");
+
+        assert_eq!(action_cost(&strike), Some(1));
+        assert_eq!(action_cost(&fireball), Some(2));
+        assert_eq!(action_cost(&ability("Tough", "Passive")), None);
+
+        encounter.use_ability(&fireball).unwrap();
+        encounter.use_ability(&strike).unwrap();
+        assert_eq!(encounter.actions_remaining, 0);
+        assert!(encounter.use_ability(&strike).is_err());
+
+        encounter.next_round();
+        assert_eq!(encounter.round, 2);
+        assert_eq!(encounter.actions_remaining, ACTIONS_PER_TURN);
+    }
+
+    #[test]
+    fn test_punish_reactions() {
+        let mut encounter = Encounter::default();
+        let riposte = ability("Riposte", "Punish");
+        let trip = ability("Trip", "Punish, Maneuver");
+
+        assert!(encounter.punish_available(&riposte));
+        encounter.use_ability(&riposte).unwrap();
+        assert_eq!(encounter.actions_remaining, ACTIONS_PER_TURN);
+        assert!(!encounter.punish_available(&riposte));
+        assert!(encounter.use_ability(&trip).is_err());
+
+        encounter.next_round();
+        assert!(encounter.punish_available(&riposte));
+        assert!(encounter.punishes_used.is_empty());
+    }
+
+    #[test]
+    fn test_damage_log() {
+        let mut encounter = Encounter::default();
+        assert_eq!(encounter.take_damage(5, 2, "Goblin"), 3);
+        assert_eq!(encounter.take_damage(1, 2, "Goblin"), 0);
+        encounter.next_round();
+        encounter.deal_damage(7, "Goblin");
+
+        assert_eq!(encounter.damage_taken(), 3);
+        assert_eq!(encounter.damage_dealt(), 7);
+        assert_eq!(encounter.damage_log[2].round, 2);
+        assert_eq!(
+            encounter.summary(),
+            "Encounter ended after 2 rounds: took 3 damage, dealt 7"
+        );
+    }
+}
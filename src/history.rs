@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+
+/// Number of undo steps kept; older ones are dropped.
+pub const HISTORY_LIMIT: usize = 100;
+
+/// Bounded undo/redo stacks of snapshots. Consecutive edits that share a
+/// group (e.g. typing into the same field) collapse into one undo step.
+#[derive(Debug, Clone)]
+pub struct History<T, G> {
+    undo: VecDeque<T>,
+    redo: Vec<T>,
+    group: Option<G>,
+    limit: usize,
+}
+
+impl<T, G: PartialEq> Default for History<T, G> {
+    fn default() -> Self {
+        Self::with_limit(HISTORY_LIMIT)
+    }
+}
+
+impl<T, G: PartialEq> History<T, G> {
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            group: None,
+            limit,
+        }
+    }
+
+    /// Records the state from before a change. Nothing is pushed when the
+    /// change continues the group of the previous one.
+    pub fn record(&mut self, before: T, group: Option<G>) {
+        self.redo.clear();
+        if group.is_some() && group == self.group {
+            return;
+        }
+        self.undo.push_back(before);
+        if self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+        self.group = group;
+    }
+
+    /// Returns the state to go back to, keeping `current` for redo.
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let previous = self.undo.pop_back()?;
+        self.redo.push(current);
+        self.group = None;
+        Some(previous)
+    }
+
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let next = self.redo.pop()?;
+        self.undo.push_back(current);
+        self.group = None;
+        Some(next)
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.group = None;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history: History<i32, ()> = History::default();
+        history.record(1, None);
+        history.record(2, None);
+
+        assert_eq!(history.undo(3), Some(2));
+        assert_eq!(history.undo(2), Some(1));
+        assert_eq!(history.undo(1), None);
+        assert_eq!(history.redo(1), Some(2));
+        assert!(history.can_undo());
+
+        // A new change discards whatever could have been redone.
+        history.record(2, None);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn test_grouped_edits() {
+        let mut history: History<&str, usize> = History::default();
+        history.record("", Some(0));
+        history.record("a", Some(0));
+        history.record("ab", Some(0));
+        history.record("abc", Some(1));
+
+        assert_eq!(history.undo("abcd"), Some("abc"));
+        assert_eq!(history.undo("abc"), Some(""));
+        assert!(!history.can_undo());
+
+        // Undoing ends the group, so typing again starts a new step.
+        history.record("", Some(0));
+        assert_eq!(history.undo("a"), Some(""));
+        history.record("", Some(0));
+        history.record("b", Some(0));
+        assert_eq!(history.undo("bc"), Some(""));
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut history: History<usize, ()> = History::with_limit(3);
+        for i in 0..5 {
+            history.record(i, None);
+        }
+        assert_eq!(history.undo(5), Some(4));
+        assert_eq!(history.undo(4), Some(3));
+        assert_eq!(history.undo(3), Some(2));
+        assert_eq!(history.undo(2), None);
+    }
+}
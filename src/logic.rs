@@ -1,144 +1,851 @@
-use crate::model::{Character, Origin};
+use crate::message::AttributeField;
+use crate::model::{
+    Ability, ArmourProfile, Character, Condition, ConditionDuration, DurationKind, Item, LevelUpRecord,
+    Modifier, StatTarget, WeaponProfile,
+};
+use crate::origins::{self, OriginDef, OriginRules};
 
-pub fn calculate_max_hp(char: &Character) -> i32 {
-    let base = 2 * char.level + 3 * char.attributes.endurance;
-    let max = match char.origin {
-        Origin::GiantKin => 3 * char.level + 3 * char.attributes.endurance,
-        _ => base,
-    };
-    (max - (char.wounds * char.attributes.endurance) + char.max_hp_offset).max(1)
+pub const MAX_LEVEL: i32 = 10;
+/// Lowest value an attribute can be set to on the sheet.
+pub const MIN_ATTRIBUTE_VALUE: i32 = 1;
+/// Attribute points a character may spend when gaining a level.
+pub const LEVEL_UP_ATTRIBUTE_POINTS: i32 = 1;
+
+/// Speed lost while wearing slow armour (or armour the character is too weak for).
+pub const SLOW_ARMOUR_SPEED_PENALTY: i32 = 1;
+
+pub fn attribute_value(char: &Character, field: AttributeField) -> i32 {
+    char.attributes.get(field)
+}
+
+/// Every modifier currently in effect: the character's own, those on
+/// equipped items, prepared abilities and active conditions.
+pub fn active_modifiers(char: &Character) -> impl Iterator<Item = &Modifier> {
+    let items = char
+        .inventory
+        .iter()
+        .filter(|item| item.equipped)
+        .flat_map(|item| &item.modifiers);
+    let abilities = char
+        .abilities
+        .iter()
+        .filter(|ability| ability.prepared)
+        .flat_map(|ability| &ability.modifiers);
+    let conditions = char.conditions.iter().flat_map(|c| &c.modifiers);
+    char.modifiers
+        .iter()
+        .chain(items)
+        .chain(abilities)
+        .chain(conditions)
+        .filter(|m| m.active)
+}
+
+/// One labelled contribution to a derived stat, e.g. `Dexterity +2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BreakdownPart {
+    pub label: String,
+    pub value: i32,
+}
+
+/// A derived stat together with the parts it was summed from. The parts
+/// always add up to `total`; clamping shows up as its own, final part.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Breakdown {
+    pub total: i32,
+    pub parts: Vec<BreakdownPart>,
+    pub clamped: bool,
+}
+
+impl Breakdown {
+    fn base(label: impl Into<String>, value: i32) -> Self {
+        Self {
+            total: value,
+            parts: vec![BreakdownPart {
+                label: label.into(),
+                value,
+            }],
+            clamped: false,
+        }
+    }
+
+    /// Adds a part, skipping it when it contributes nothing.
+    fn add(&mut self, label: impl Into<String>, value: i32) {
+        if value != 0 {
+            self.total += value;
+            self.parts.push(BreakdownPart {
+                label: label.into(),
+                value,
+            });
+        }
+    }
+
+    fn add_modifiers(&mut self, char: &Character, target: StatTarget) {
+        for modifier in active_modifiers(char).filter(|m| m.target == target) {
+            let label = if modifier.source.trim().is_empty() {
+                "Modifier"
+            } else {
+                modifier.source.as_str()
+            };
+            self.add(label, modifier.value);
+        }
+    }
+
+    fn clamp(mut self, min: i32, max: i32) -> Self {
+        let clamped = self.total.clamp(min, max);
+        self.clamped = clamped != self.total;
+        if clamped < self.total {
+            self.add(format!("Capped at {}", max), clamped - self.total);
+        } else if clamped > self.total {
+            self.add(format!("Minimum {}", min), clamped - self.total);
+        }
+        self
+    }
+
+    /// The total before any minimum or cap was applied.
+    pub fn unclamped(&self) -> i32 {
+        match self.parts.last() {
+            Some(part) if self.clamped => self.total - part.value,
+            _ => self.total,
+        }
+    }
+
+    /// One line per part, e.g. `Base 6` then `Dexterity +2`.
+    pub fn lines(&self) -> Vec<String> {
+        self.parts
+            .iter()
+            .enumerate()
+            .map(|(i, part)| {
+                if i == 0 {
+                    format!("{} {}", part.label, part.value)
+                } else {
+                    format!("{} {:+}", part.label, part.value)
+                }
+            })
+            .collect()
+    }
 }
 
-pub fn calculate_movement_speed(char: &Character) -> i32 {
+/// Labels an origin-driven part with the origin's name when its rule differs
+/// from the standard one.
+fn origin_label(char: &Character, defs: &[OriginDef], label: String, differs: bool) -> String {
+    if differs {
+        format!("{} ({})", label, origins::name(defs, &char.origin))
+    } else {
+        label
+    }
+}
+
+pub fn max_hp_breakdown(char: &Character, defs: &[OriginDef]) -> Breakdown {
+    let mut breakdown = unmodified_max_hp_breakdown(char, defs);
+    breakdown.add_modifiers(char, StatTarget::MaxHp);
+    breakdown.clamp(1, i32::MAX)
+}
+
+/// Max HP from level, Endurance and wounds alone, before modifiers and the
+/// minimum of 1 apply.
+pub fn unmodified_max_hp(char: &Character, defs: &[OriginDef]) -> i32 {
+    unmodified_max_hp_breakdown(char, defs).total
+}
+
+fn unmodified_max_hp_breakdown(char: &Character, defs: &[OriginDef]) -> Breakdown {
+    let rules = origins::rules(defs, &char.origin);
+    let standard = OriginRules::default();
+    let end = char.attributes.endurance;
+
+    let mut breakdown = Breakdown::base(
+        origin_label(
+            char,
+            defs,
+            format!("Level {} × {}", char.level, rules.hp_per_level),
+            rules.hp_per_level != standard.hp_per_level,
+        ),
+        rules.hp_per_level * char.level,
+    );
+    breakdown.add(
+        origin_label(
+            char,
+            defs,
+            format!("Endurance {} × {}", end, rules.hp_per_endurance),
+            rules.hp_per_endurance != standard.hp_per_endurance,
+        ),
+        rules.hp_per_endurance * end,
+    );
+    let wounds = char.wounds.len() as i32;
+    breakdown.add(format!("Wounds {} × {}", wounds, end), -(wounds * end));
+    breakdown
+}
+
+pub fn calculate_max_hp(char: &Character, defs: &[OriginDef]) -> i32 {
+    max_hp_breakdown(char, defs).total
+}
+
+/// True once wounds have pushed max HP down to the floor of 1, where the
+/// clamp would otherwise hide that the character is dying.
+pub fn wounds_at_hp_floor(char: &Character, defs: &[OriginDef]) -> bool {
+    !char.wounds.is_empty() && max_hp_breakdown(char, defs).unclamped() <= 1
+}
+
+/// Heals up to `count` wounds that can heal on a rest, oldest first, and
+/// returns their descriptions.
+pub fn heal_wounds_on_rest(char: &mut Character, count: i32) -> Vec<String> {
+    let mut healed = Vec::new();
+    char.wounds.retain(|wound| {
+        if (healed.len() as i32) < count && wound.heals_on_rest() {
+            healed.push(wound.description.clone());
+            false
+        } else {
+            true
+        }
+    });
+    healed
+}
+
+pub fn movement_speed_breakdown(char: &Character, defs: &[OriginDef]) -> Breakdown {
     let dex_half = (char.attributes.dexterity as f32 / 2.0).ceil() as i32;
-    (2 + dex_half + char.speed_offset).max(0)
+    let mut breakdown = Breakdown::base("Base", 2);
+    breakdown.add("Dexterity (half, rounded up)", dex_half);
+    if is_slowed_by_armour(char, defs) {
+        breakdown.add("Slowed by armour", -SLOW_ARMOUR_SPEED_PENALTY);
+    }
+    breakdown.add_modifiers(char, StatTarget::Speed);
+    breakdown.clamp(0, i32::MAX)
 }
 
-pub fn calculate_carrying_slots(char: &Character) -> i32 {
-    let base = 4 + char.attributes.strength;
-    let total = match char.origin {
-        Origin::Dwarf => 6 + char.attributes.strength,
-        _ => base,
-    };
-    (total + char.max_inventory_slots_offset).max(0)
+pub fn calculate_movement_speed(char: &Character, defs: &[OriginDef]) -> i32 {
+    movement_speed_breakdown(char, defs).total
+}
+
+pub fn carrying_slots_breakdown(char: &Character, defs: &[OriginDef]) -> Breakdown {
+    let base = origins::rules(defs, &char.origin).carrying_slots_base;
+    let mut breakdown = Breakdown::base(
+        origin_label(
+            char,
+            defs,
+            "Base".to_string(),
+            base != OriginRules::default().carrying_slots_base,
+        ),
+        base,
+    );
+    breakdown.add("Strength", char.attributes.strength);
+    breakdown.add_modifiers(char, StatTarget::CarryingSlots);
+    breakdown.clamp(0, i32::MAX)
+}
+
+pub fn calculate_carrying_slots(char: &Character, defs: &[OriginDef]) -> i32 {
+    carrying_slots_breakdown(char, defs).total
+}
+
+pub fn calculate_used_slots(char: &Character) -> i32 {
+    char.inventory.iter().map(|item| item.slots.max(0)).sum()
+}
+
+pub fn calculate_tender(char: &Character) -> i32 {
+    char.ledger.iter().map(|entry| entry.amount).sum()
+}
+
+pub fn max_attribute_value(level: i32) -> i32 {
+    (level + 3).min(10)
+}
+
+/// Total XP needed for the next level, or `None` at the level cap.
+pub fn xp_for_next_level(char: &Character) -> Option<i32> {
+    if char.level >= MAX_LEVEL {
+        return None;
+    }
+    char.xp_thresholds.get((char.level - 1).max(0) as usize).copied()
+}
+
+pub fn can_level_up(char: &Character) -> bool {
+    xp_for_next_level(char).is_some_and(|needed| char.xp >= needed)
+}
+
+/// Raises the character one level, applying the chosen attribute increases,
+/// and returns a record of what changed.
+pub fn apply_level_up(
+    char: &mut Character,
+    defs: &[OriginDef],
+    increases: &[AttributeField],
+) -> LevelUpRecord {
+    let from_level = char.level;
+    let max_hp_before = calculate_max_hp(char, defs);
+    let prepared_slots_before = calculate_prepared_slots(char);
+
+    char.level = (char.level + 1).min(MAX_LEVEL);
+    let attr_max = max_attribute_value(char.level);
+    let mut attribute_increases = Vec::new();
+    for field in increases {
+        let value = char.attributes.get_mut(*field);
+        if *value < attr_max {
+            *value += 1;
+            attribute_increases.push(format!("{} {}", field, value));
+        }
+    }
+
+    let max_hp_after = calculate_max_hp(char, defs);
+    char.current_hp = (char.current_hp + max_hp_after - max_hp_before).clamp(0, max_hp_after);
+
+    LevelUpRecord {
+        timestamp: crate::timestamp::now(),
+        from_level,
+        to_level: char.level,
+        max_hp_before,
+        max_hp_after,
+        prepared_slots_before,
+        prepared_slots_after: calculate_prepared_slots(char),
+        attribute_increases,
+    }
+}
+
+pub fn prepared_slots_breakdown(char: &Character) -> Breakdown {
+    let mut breakdown = Breakdown::base("Base", 2);
+    breakdown.add("Level", char.level);
+    breakdown.add_modifiers(char, StatTarget::PreparedAbilities);
+    breakdown.clamp(0, i32::MAX)
 }
 
 pub fn calculate_prepared_slots(char: &Character) -> i32 {
-    (2 + char.level + char.max_abilities_offset).max(0)
+    prepared_slots_breakdown(char).total
+}
+
+pub fn spell_slots_breakdown(char: &Character) -> Breakdown {
+    let mut breakdown = Breakdown::base("Intelligence", char.attributes.intelligence);
+    breakdown.add_modifiers(char, StatTarget::SpellSlots);
+    breakdown.clamp(0, i32::MAX)
+}
+
+pub fn prepared_count(char: &Character) -> i32 {
+    char.abilities.iter().filter(|a| a.prepared).count() as i32
+}
+
+/// Prepares or unprepares one ability, refusing to go past the prepared limit.
+pub fn set_prepared(char: &mut Character, idx: usize, prepared: bool) -> Result<(), String> {
+    let slots = calculate_prepared_slots(char);
+    let count = prepared_count(char);
+    let Some(ability) = char.abilities.get_mut(idx) else {
+        return Ok(());
+    };
+    if prepared && !ability.prepared && count >= slots {
+        return Err(format!(
+            "All {} prepared slots are in use; unprepare an ability first.",
+            slots
+        ));
+    }
+    ability.prepared = prepared;
+    Ok(())
+}
+
+/// Prepares exactly the named abilities, unpreparing the rest. Fails without
+/// changing anything if the set is larger than the prepared limit; returns
+/// the names that matched no ability.
+pub fn apply_loadout(char: &mut Character, names: &[String]) -> Result<Vec<String>, String> {
+    let wanted = char
+        .abilities
+        .iter()
+        .filter(|a| names.contains(&a.name))
+        .count() as i32;
+    let slots = calculate_prepared_slots(char);
+    if wanted > slots {
+        return Err(format!(
+            "{} abilities selected but only {} can be prepared.",
+            wanted, slots
+        ));
+    }
+    for ability in &mut char.abilities {
+        ability.prepared = names.contains(&ability.name);
+    }
+    Ok(names
+        .iter()
+        .filter(|name| !char.abilities.iter().any(|a| &&a.name == name))
+        .cloned()
+        .collect())
 }
 
 pub fn calculate_spell_slots(char: &Character) -> i32 {
-    (char.attributes.intelligence + char.max_spells_offset).max(0)
+    spell_slots_breakdown(char).total
+}
+
+pub fn miracle_slots_breakdown(char: &Character) -> Breakdown {
+    let mut breakdown = Breakdown::base("Faith", char.attributes.faith);
+    breakdown.add_modifiers(char, StatTarget::MiracleSlots);
+    breakdown.clamp(0, i32::MAX)
 }
 
 pub fn calculate_miracle_slots(char: &Character) -> i32 {
-    (char.attributes.faith + char.max_miracles_offset).max(0)
+    miracle_slots_breakdown(char).total
+}
+
+pub fn equipped_armour(char: &Character) -> impl Iterator<Item = &ArmourProfile> {
+    equipped_armour_items(char).map(|(_, armour)| armour)
+}
+
+pub fn meets_armour_strength(char: &Character, defs: &[OriginDef], armour: &ArmourProfile) -> bool {
+    origins::rules(defs, &char.origin).ignores_armour_strength
+        || char.attributes.strength >= armour.strength
+}
+
+pub fn is_slowed_by_armour(char: &Character, defs: &[OriginDef]) -> bool {
+    let ignores_slow = origins::rules(defs, &char.origin).ignores_slow_armour;
+    equipped_armour(char)
+        .any(|a| (a.slow && !ignores_slow) || !meets_armour_strength(char, defs, a))
+}
+
+/// Equipped items that carry an armour profile.
+fn equipped_armour_items(char: &Character) -> impl Iterator<Item = (&Item, &ArmourProfile)> {
+    char.inventory
+        .iter()
+        .filter(|item| item.equipped)
+        .filter_map(|item| item.armour.as_ref().map(|a| (item, a)))
+}
+
+pub fn armor_class_breakdown(char: &Character) -> Breakdown {
+    let mut breakdown = Breakdown::base("Base", 6);
+    breakdown.add("Dexterity", char.attributes.dexterity);
+    for (item, armour) in equipped_armour_items(char) {
+        breakdown.add(&item.name, armour.ac);
+    }
+    breakdown.add("Bonus", char.armor_bonus);
+    breakdown.add_modifiers(char, StatTarget::ArmorClass);
+    breakdown
 }
 
 pub fn calculate_armor_class(char: &Character) -> i32 {
-    6 + char.attributes.dexterity + char.armor_bonus
+    armor_class_breakdown(char).total
+}
+
+pub fn damage_reduction_breakdown(char: &Character) -> Breakdown {
+    let mut breakdown = Breakdown::base("Base", 0);
+    for (item, armour) in equipped_armour_items(char) {
+        breakdown.add(&item.name, armour.dr);
+    }
+    breakdown.clamp(0, i32::MAX)
+}
+
+pub fn calculate_damage_reduction(char: &Character) -> i32 {
+    damage_reduction_breakdown(char).total
+}
+
+/// A DR qualifier is empty or a slash followed by a damage type, e.g.
+/// `/holy` or `/cold iron`. The number itself always comes from armour.
+pub fn is_valid_dr_qualifier(qualifier: &str) -> bool {
+    qualifier.is_empty()
+        || qualifier.strip_prefix('/').is_some_and(|kind| {
+            !kind.trim().is_empty()
+                && kind
+                    .chars()
+                    .all(|c| c.is_alphabetic() || c == ' ' || c == '-')
+        })
+}
+
+/// Price the character pays for a single unit of `item`, applying the
+/// origin's armour price percentage (Giant-kin pay 10% more), rounded up.
+pub fn calculate_purchase_price(char: &Character, defs: &[OriginDef], item: &Item) -> i32 {
+    if item.armour.is_some() {
+        let percent = origins::rules(defs, &char.origin).armour_price_percent;
+        (item.price * percent + 99) / 100
+    } else {
+        item.price
+    }
+}
+
+/// Strength as it counts for overcoming the Heavy property of weapons.
+pub fn calculate_heavy_strength(char: &Character, defs: &[OriginDef]) -> i32 {
+    char.attributes.strength + origins::rules(defs, &char.origin).heavy_strength_offset
+}
+
+pub fn can_wield(char: &Character, defs: &[OriginDef], weapon: &WeaponProfile) -> bool {
+    weapon.heavy <= 0 || calculate_heavy_strength(char, defs) >= weapon.heavy
+}
+
+pub fn crit_range_breakdown(char: &Character) -> Breakdown {
+    let mut breakdown = Breakdown::base("Base", 12);
+    breakdown.add("Luck (half)", -(char.attributes.luck / 2));
+    breakdown.add_modifiers(char, StatTarget::CritRange);
+    breakdown.clamp(1, 12)
 }
 
 pub fn calculate_crit_range(char: &Character) -> i32 {
-    let bonus = char.attributes.luck / 2;
-    (12 - bonus + char.crit_range_offset).clamp(1, 12)
-}
-
-pub fn get_origin_traits(origin: Origin) -> Vec<&'static str> {
-    match origin {
-        Origin::Human => vec!["Humans starting Luck is 3."],
-        Origin::Elf => vec![
-            "Elves can sense magic near to them, they can see the aura given off by magic creatures, items, spells, and miracles within 2 spaces.",
-            "Focus: Elves can focus as an action, expanding the range of their magical sight to 10 spaces for a Short duration.",
-        ],
-        Origin::HalfElf => vec![
-            "Half-Elves, due to their elvish lineage have enhanced eyesight. Transparent obstructions do not obscure their vision.",
-            "Prodigy: When you roll a Skill Check, Attack or Saving Throw, you can cast a spell to grant +1d6 Circumstance.",
-        ],
-        Origin::Dwarf => vec![
-            "Dwarves don’t need to meet the Strength requirement for armour, and slow armour does not impede their movement.",
-            "Dwarves have 6 + Strength Carrying Slots.",
-        ],
-        Origin::Orc => vec![
-            "Orcs can see in the dark as well as they can in light, though only in black and white.",
-            "Charge: When using a move action, you can move double your speed if it is in a straight line towards an Enemy.",
-        ],
-        Origin::Gnome => vec![
-            "Gnomes are small. As a consequence, treat their Strength as 2 lower for the purposes of overcoming the Heavy property of weapons.",
-            "The magic of the forest suffuses Gnomes, allowing them to roll twice to resist the effects of spells (but not miracles), taking the best result, and also providing them the ability to communicate with small animals.",
-        ],
-        Origin::Halfling => vec![
-            "Halflings are small. As a consequence, treat their Strength as 2 lower for the purposes of overcoming the Heavy property of weapons.",
-            "Move through spaces of >Small creatures",
-            "Halflings can move through spaces occupied by creatures bigger than small, and they can fit into smaller spaces than other origins.",
-        ],
-        Origin::GiantKin => vec![
-            "Giant-kin toughness benefit them with maximum Hit Points of 3 × Level + 3 × Endurance.",
-            "Giant-kin are large. As a consequence, treat their Strength as 2 higher for the purposes of overcoming the Heavy property of weapons. Additionally, armour for Giant-kin costs 10% more.",
-        ],
+    crit_range_breakdown(char).total
+}
+
+/// Moves conditions on by one combat round. Round counts tick down and
+/// expire at zero; instantaneous effects end. Returns the expired names.
+pub fn advance_conditions(char: &mut Character) -> Vec<String> {
+    let mut expired = Vec::new();
+    char.conditions.retain_mut(|condition| {
+        let keep = match &mut condition.duration {
+            ConditionDuration::Rounds(n) => {
+                *n = n.saturating_sub(1);
+                *n > 0
+            }
+            ConditionDuration::Instantaneous => false,
+            ConditionDuration::Short | ConditionDuration::Long => true,
+        };
+        if !keep {
+            expired.push(condition.name.clone());
+        }
+        keep
+    });
+    expired
+}
+
+/// Ends every condition whose duration is one of `kinds`, returning their names.
+pub fn end_conditions(char: &mut Character, kinds: &[DurationKind]) -> Vec<String> {
+    let (ended, kept) = std::mem::take(&mut char.conditions)
+        .into_iter()
+        .partition(|c| kinds.contains(&c.duration.kind()));
+    char.conditions = kept;
+    ended.into_iter().map(|c: Condition| c.name).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotKind {
+    Spell,
+    Miracle,
+}
+
+impl std::fmt::Display for SlotKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                SlotKind::Spell => "spell slot",
+                SlotKind::Miracle => "miracle slot",
+            }
+        )
+    }
+}
+
+/// The slot an ability spends when used, from its "Spell" or "Miracle" tag.
+pub fn ability_slot(ability: &Ability) -> Option<SlotKind> {
+    if ability.has_tag("Spell") {
+        Some(SlotKind::Spell)
+    } else if ability.has_tag("Miracle") {
+        Some(SlotKind::Miracle)
+    } else {
+        None
+    }
+}
+
+pub fn slots_remaining(char: &Character, kind: SlotKind) -> i32 {
+    match kind {
+        SlotKind::Spell => calculate_spell_slots(char) - char.expended_spell_slots,
+        SlotKind::Miracle => calculate_miracle_slots(char) - char.expended_miracle_slots,
+    }
+    .max(0)
+}
+
+/// Expends one slot of `kind`, refusing when none are left.
+pub fn spend_slot(char: &mut Character, kind: SlotKind) -> Result<(), String> {
+    if slots_remaining(char, kind) == 0 {
+        return Err(format!("No {}s left.", kind));
     }
+    match kind {
+        SlotKind::Spell => char.expended_spell_slots += 1,
+        SlotKind::Miracle => char.expended_miracle_slots += 1,
+    }
+    Ok(())
 }
 
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{Character, Origin};
+    use crate::model::{
+        Ability, ArmourProfile, Character, Item, LedgerEntry, Modifier, Origin, StatTarget,
+        WeaponProfile, Wound, WoundHealing,
+    };
 
     #[test]
     fn test_hp_calculation() {
+        let defs = origins::builtin_origins();
         let mut char = Character::default();
         char.level = 1;
         char.attributes.endurance = 2;
 
-        assert_eq!(calculate_max_hp(&char), 8);
+        assert_eq!(calculate_max_hp(&char, &defs), 8);
 
-        char.origin = Origin::GiantKin;
-        assert_eq!(calculate_max_hp(&char), 9);
+        char.origin = Origin::new("GiantKin");
+        assert_eq!(calculate_max_hp(&char, &defs), 9);
     }
 
     #[test]
     fn test_hp_calculation_with_wounds() {
+        let defs = origins::builtin_origins();
         let mut char = Character::default();
         char.level = 1;
         char.attributes.endurance = 2;
         // Base: 8
 
-        char.wounds = 1;
+        char.wounds = vec![Wound::default(); 1];
         // 8 - (1 * 2) = 6
-        assert_eq!(calculate_max_hp(&char), 6);
+        assert_eq!(calculate_max_hp(&char, &defs), 6);
 
-        char.wounds = 3;
+        char.wounds = vec![Wound::default(); 3];
         // 8 - (3 * 2) = 2
-        assert_eq!(calculate_max_hp(&char), 2);
+        assert_eq!(calculate_max_hp(&char, &defs), 2);
+        assert!(!wounds_at_hp_floor(&char, &defs));
 
-        char.wounds = 4;
+        char.wounds = vec![Wound::default(); 4];
         // 8 - (4 * 2) = 0 -> clamped to 1
-        assert_eq!(calculate_max_hp(&char), 1);
+        assert_eq!(calculate_max_hp(&char, &defs), 1);
+        assert!(wounds_at_hp_floor(&char, &defs));
+    }
+
+    #[test]
+    fn test_wound_healing() {
+        let mut char = Character::default();
+        char.wounds = vec![
+            Wound::new("Broken arm", WoundHealing::Treatment),
+            Wound::new("Lost eye", WoundHealing::Permanent),
+            Wound::new("Cut", WoundHealing::Rest),
+            Wound::new("Bruise", WoundHealing::Rest),
+        ];
+
+        assert_eq!(heal_wounds_on_rest(&mut char, 1), vec!["Cut"]);
+
+        char.wounds[0].treated = true;
+        assert_eq!(heal_wounds_on_rest(&mut char, 5), vec!["Broken arm", "Bruise"]);
+        assert_eq!(char.wounds.len(), 1);
+        assert_eq!(char.wounds[0].description, "Lost eye");
     }
 
     #[test]
     fn test_carrying_capacity() {
+        let defs = origins::builtin_origins();
         let mut char = Character::default();
         char.attributes.strength = 3;
 
-        assert_eq!(calculate_carrying_slots(&char), 7);
+        assert_eq!(calculate_carrying_slots(&char, &defs), 7);
+
+        char.origin = Origin::new("Dwarf");
+        assert_eq!(calculate_carrying_slots(&char, &defs), 9);
+    }
+
+    #[test]
+    fn test_used_slots() {
+        let mut char = Character::default();
+        assert_eq!(calculate_used_slots(&char), 0);
 
-        char.origin = Origin::Dwarf;
-        assert_eq!(calculate_carrying_slots(&char), 9);
+        let mut tent = Item::new("Tent");
+        tent.slots = 2;
+        let mut arrows = Item::new("Arrows");
+        arrows.quantity = 20;
+        char.inventory = vec![tent, arrows, Item::new("Rope")];
+        assert_eq!(calculate_used_slots(&char), 4);
     }
 
     #[test]
     fn test_movement_speed() {
+        let defs = origins::builtin_origins();
         let mut char = Character::default();
 
         char.attributes.dexterity = 1;
-        assert_eq!(calculate_movement_speed(&char), 3);
+        assert_eq!(calculate_movement_speed(&char, &defs), 3);
 
         char.attributes.dexterity = 2;
-        assert_eq!(calculate_movement_speed(&char), 3);
+        assert_eq!(calculate_movement_speed(&char, &defs), 3);
 
         char.attributes.dexterity = 3;
-        assert_eq!(calculate_movement_speed(&char), 4);
+        assert_eq!(calculate_movement_speed(&char, &defs), 4);
+    }
+
+    #[test]
+    fn test_heavy_weapons_by_origin() {
+        let defs = origins::builtin_origins();
+        let mut char = Character::default();
+        char.attributes.strength = 3;
+        let greatsword = WeaponProfile {
+            damage: "1d12".to_string(),
+            heavy: 3,
+            properties: String::new(),
+        };
+
+        assert!(can_wield(&char, &defs, &greatsword));
+
+        char.origin = Origin::new("Gnome");
+        assert_eq!(calculate_heavy_strength(&char, &defs), 1);
+        assert!(!can_wield(&char, &defs, &greatsword));
+
+        char.origin = Origin::new("Halfling");
+        assert!(!can_wield(&char, &defs, &greatsword));
+
+        char.origin = Origin::new("GiantKin");
+        char.attributes.strength = 1;
+        assert_eq!(calculate_heavy_strength(&char, &defs), 3);
+        assert!(can_wield(&char, &defs, &greatsword));
+
+        char.attributes.strength = 0;
+        assert!(can_wield(&char, &defs, &WeaponProfile::default()));
+    }
+
+    fn chain_mail() -> Item {
+        let mut item = Item::new("Chain Mail");
+        item.price = 95;
+        item.equipped = true;
+        item.armour = Some(ArmourProfile {
+            ac: 3,
+            dr: 1,
+            strength: 3,
+            slow: true,
+        });
+        item
+    }
+
+    #[test]
+    fn test_equipped_armour() {
+        let defs = origins::builtin_origins();
+        let mut char = Character::default();
+        char.attributes.dexterity = 2;
+        char.attributes.strength = 3;
+        char.inventory.push(chain_mail());
+
+        assert_eq!(calculate_armor_class(&char), 11);
+        assert_eq!(calculate_damage_reduction(&char), 1);
+        assert!(is_slowed_by_armour(&char, &defs));
+        assert_eq!(calculate_movement_speed(&char, &defs), 2);
+
+        char.inventory[0].equipped = false;
+        assert_eq!(calculate_armor_class(&char), 8);
+        assert_eq!(calculate_damage_reduction(&char), 0);
+        assert_eq!(calculate_movement_speed(&char, &defs), 3);
+    }
+
+    #[test]
+    fn test_armour_strength_requirement() {
+        let defs = origins::builtin_origins();
+        let mut char = Character::default();
+        char.attributes.strength = 1;
+        let mut mail = chain_mail();
+        if let Some(armour) = &mut mail.armour {
+            armour.slow = false;
+        }
+        char.inventory.push(mail);
+
+        assert!(is_slowed_by_armour(&char, &defs));
+
+        char.attributes.strength = 3;
+        assert!(!is_slowed_by_armour(&char, &defs));
+    }
+
+    #[test]
+    fn test_dwarf_armour_exemptions() {
+        let defs = origins::builtin_origins();
+        let mut char = Character::default();
+        char.origin = Origin::new("Dwarf");
+        char.attributes.strength = 1;
+        char.inventory.push(chain_mail());
+
+        assert!(meets_armour_strength(&char, &defs, char.inventory[0].armour.as_ref().unwrap()));
+        assert!(!is_slowed_by_armour(&char, &defs));
+        assert_eq!(calculate_movement_speed(&char, &defs), 3);
+    }
+
+    #[test]
+    fn test_giant_kin_armour_price() {
+        let defs = origins::builtin_origins();
+        let mut char = Character::default();
+        let mail = chain_mail();
+        let rope = {
+            let mut rope = Item::new("Rope");
+            rope.price = 10;
+            rope
+        };
+
+        assert_eq!(calculate_purchase_price(&char, &defs, &mail), 95);
+
+        char.origin = Origin::new("GiantKin");
+        assert_eq!(calculate_purchase_price(&char, &defs, &mail), 105);
+        assert_eq!(calculate_purchase_price(&char, &defs, &rope), 10);
+    }
+
+    #[test]
+    fn test_tender_balance() {
+        let mut char = Character::default();
+        assert_eq!(calculate_tender(&char), 200);
+
+        char.ledger.push(LedgerEntry::new(-95, "Bought Chain Mail", None));
+        char.ledger.push(LedgerEntry::new(12, "Sold Rope", None));
+        assert_eq!(calculate_tender(&char), 117);
+    }
+
+    #[test]
+    fn test_xp_thresholds() {
+        let mut char = Character::default();
+        assert_eq!(xp_for_next_level(&char), Some(10));
+        assert!(!can_level_up(&char));
+
+        char.xp = 10;
+        assert!(can_level_up(&char));
+
+        char.level = 10;
+        assert_eq!(xp_for_next_level(&char), None);
+        assert!(!can_level_up(&char));
+    }
+
+    #[test]
+    fn test_apply_level_up() {
+        let defs = origins::builtin_origins();
+        let mut char = Character::default();
+        char.attributes.endurance = 2;
+        char.current_hp = 8;
+
+        let record = apply_level_up(&mut char, &defs, &[AttributeField::Endurance]);
+        assert_eq!(char.level, 2);
+        assert_eq!(char.attributes.endurance, 3);
+        assert_eq!(record.from_level, 1);
+        assert_eq!(record.to_level, 2);
+        assert_eq!(record.max_hp_before, 8);
+        assert_eq!(record.max_hp_after, 13);
+        assert_eq!(record.prepared_slots_before, 3);
+        assert_eq!(record.prepared_slots_after, 4);
+        assert_eq!(record.attribute_increases, vec!["Endurance 3".to_string()]);
+        assert_eq!(char.current_hp, 13);
+    }
+
+    #[test]
+    fn test_level_up_respects_attribute_cap() {
+        let defs = origins::builtin_origins();
+        let mut char = Character::default();
+        char.attributes.strength = 5;
+
+        let record = apply_level_up(&mut char, &defs, &[AttributeField::Strength]);
+        assert_eq!(char.attributes.strength, 5);
+        assert!(record.attribute_increases.is_empty());
+    }
+
+    #[test]
+    fn test_modifiers_stack_by_source() {
+        let defs = origins::builtin_origins();
+        let mut char = Character::default();
+        char.attributes.endurance = 2;
+        char.modifiers.push(Modifier::new("Blessing", StatTarget::MaxHp, 1));
+
+        let mut disabled = Modifier::new("Curse", StatTarget::MaxHp, -3);
+        disabled.active = false;
+        char.modifiers.push(disabled);
+
+        let mut ring = Item::new("Ring of Vigor");
+        ring.modifiers.push(Modifier::new("Ring of Vigor", StatTarget::MaxHp, 2));
+        char.inventory.push(ring);
+
+        char.abilities.push(Ability {
+            name: "Toughness".to_string(),
+            modifiers: vec![Modifier::new("Toughness", StatTarget::MaxHp, 4)],
+            ..Ability::default()
+        });
+
+        // Ring not equipped, ability not prepared: base 8 + 1
+        assert_eq!(calculate_max_hp(&char, &defs), 9);
+
+        char.inventory[0].equipped = true;
+        assert_eq!(calculate_max_hp(&char, &defs), 11);
+
+        char.abilities[0].prepared = true;
+        assert_eq!(calculate_max_hp(&char, &defs), 15);
+
+        char.modifiers[1].active = true;
+        assert_eq!(calculate_max_hp(&char, &defs), 12);
     }
 
     #[test]
@@ -157,4 +864,127 @@ mod tests {
         char.attributes.luck = 6;
         assert_eq!(calculate_crit_range(&char), 9);
     }
+
+    #[test]
+    fn test_prepared_limit_and_loadouts() {
+        let mut char = Character {
+            abilities: ["Sneak", "Parley", "Lockpick", "Charm"]
+                .iter()
+                .map(|name| Ability {
+                    name: name.to_string(),
+                    ..Ability::default()
+                })
+                .collect(),
+            ..Character::default()
+        };
+        assert_eq!(calculate_prepared_slots(&char), 3);
+
+        for i in 0..3 {
+            set_prepared(&mut char, i, true).unwrap();
+        }
+        assert!(set_prepared(&mut char, 3, true).is_err());
+        assert!(!char.abilities[3].prepared);
+        set_prepared(&mut char, 0, false).unwrap();
+        set_prepared(&mut char, 3, true).unwrap();
+        assert_eq!(prepared_count(&char), 3);
+
+        let social = vec!["Parley".to_string(), "Charm".to_string(), "Sing".to_string()];
+        assert_eq!(apply_loadout(&mut char, &social), Ok(vec!["Sing".to_string()]));
+        assert_eq!(prepared_count(&char), 2);
+        assert!(char.abilities[1].prepared && char.abilities[3].prepared);
+
+        let everything: Vec<String> = char.abilities.iter().map(|a| a.name.clone()).collect();
+        assert!(apply_loadout(&mut char, &everything).is_err());
+        assert_eq!(prepared_count(&char), 2);
+    }
+
+    #[test]
+    fn test_spend_slots() {
+        let mut char = Character::default();
+        char.attributes.intelligence = 1;
+        char.attributes.faith = 0;
+        let spell = Ability {
+            name: "Spark".to_string(),
+            tags: "Spell, 1 Action".to_string(),
+            ..Ability::default()
+        };
+        let miracle = Ability {
+            name: "Mend".to_string(),
+            tags: "miracle".to_string(),
+            ..Ability::default()
+        };
+
+        assert_eq!(ability_slot(&spell), Some(SlotKind::Spell));
+        assert_eq!(ability_slot(&miracle), Some(SlotKind::Miracle));
+        assert_eq!(ability_slot(&Ability::default()), None);
+
+        assert!(spend_slot(&mut char, SlotKind::Spell).is_ok());
+        assert_eq!(char.expended_spell_slots, 1);
+        assert_eq!(
+            spend_slot(&mut char, SlotKind::Spell),
+            Err("No spell slots left.".to_string())
+        );
+        assert!(spend_slot(&mut char, SlotKind::Miracle).is_err());
+        assert_eq!(char.expended_miracle_slots, 0);
+    }
+
+    #[test]
+    fn test_conditions_expire() {
+        let defs = origins::builtin_origins();
+        let mut char = Character::default();
+        let mut haste = Condition::new("Haste", ConditionDuration::Rounds(2));
+        haste.modifiers.push(Modifier::new("Haste", StatTarget::Speed, 2));
+        char.conditions = vec![
+            haste,
+            Condition::new("Stagger", ConditionDuration::Instantaneous),
+            Condition::new("Focus", ConditionDuration::Short),
+            Condition::new("Blessed", ConditionDuration::Long),
+        ];
+        let speed = calculate_movement_speed(&char, &defs);
+
+        assert_eq!(advance_conditions(&mut char), vec!["Stagger"]);
+        assert!(matches!(char.conditions[0].duration, ConditionDuration::Rounds(1)));
+        assert_eq!(calculate_movement_speed(&char, &defs), speed);
+
+        assert_eq!(advance_conditions(&mut char), vec!["Haste"]);
+        assert_eq!(calculate_movement_speed(&char, &defs), speed - 2);
+
+        assert_eq!(end_conditions(&mut char, &[DurationKind::Short]), vec!["Focus"]);
+        assert_eq!(char.conditions.len(), 1);
+        assert_eq!(char.conditions[0].name, "Blessed");
+    }
+
+    #[test]
+    fn test_breakdowns_explain_totals() {
+        let defs = origins::builtin_origins();
+        let mut char = Character::default();
+        char.attributes.dexterity = 2;
+        char.attributes.strength = 3;
+        char.armor_bonus = 1;
+        char.inventory.push(chain_mail());
+        char.modifiers.push(Modifier::new("Shield of Faith", StatTarget::ArmorClass, 1));
+
+        let ac = armor_class_breakdown(&char);
+        assert_eq!(ac.total, 13);
+        assert_eq!(
+            ac.lines(),
+            vec!["Base 6", "Dexterity +2", "Chain Mail +3", "Bonus +1", "Shield of Faith +1"]
+        );
+
+        char.origin = Origin::new("GiantKin");
+        char.attributes.endurance = 1;
+        char.wounds = vec![Wound::default(); 2];
+        let hp = max_hp_breakdown(&char, &defs);
+        assert_eq!(hp.total, 4);
+        assert_eq!(
+            hp.lines(),
+            vec!["Level 1 × 3 (Giant-kin) 3", "Endurance 1 × 3 +3", "Wounds 2 × 1 -2"]
+        );
+
+        char.wounds = vec![Wound::default(); 9];
+        let hp = max_hp_breakdown(&char, &defs);
+        assert_eq!(hp.total, 1);
+        assert_eq!(hp.parts.last().unwrap().label, "Minimum 1");
+        assert_eq!(hp.parts.iter().map(|p| p.value).sum::<i32>(), 1);
+    }
 }
@@ -1,29 +1,100 @@
 #![windows_subsystem = "windows"]
 
 mod app;
+mod backup;
+mod creation;
+mod dice;
+mod encounter;
+mod history;
 mod logic;
 mod message;
+mod migration;
 mod model;
+mod origins;
 mod parser;
+mod recovery;
+mod rest;
+mod salvage;
+mod timestamp;
+mod validation;
 mod view;
 
 use app::CharacterSheet;
 use iced::Theme;
 
 pub fn main() -> iced::Result {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("--validate") {
+        attach_parent_console();
+        std::process::exit(validate_files(&args[1..]));
+    }
+
     let icon =
         iced::window::icon::from_file_data(include_bytes!("../resources/icon.png"), None).unwrap();
 
     iced::application(CharacterSheet::new, CharacterSheet::update, view::view)
-        .title("Bastion Character Sheet")
+        .title(CharacterSheet::title)
         .window(iced::window::Settings {
             icon: Some(icon),
+            exit_on_close_request: false,
             ..Default::default()
         })
         .theme(theme)
+        .subscription(CharacterSheet::subscription)
         .run()
 }
 
+/// Release builds use the Windows GUI subsystem, which starts without a
+/// console, so `--validate` output would go nowhere. Borrow the console of
+/// the shell that launched us instead.
+#[cfg(windows)]
+fn attach_parent_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    unsafe extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // Fails harmlessly when there is no parent console, e.g. from Explorer.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
+
+/// Prints a validation report for each character file and returns the exit
+/// code: 0 when every file is valid, 1 on rule errors, 2 on unreadable files.
+fn validate_files(paths: &[String]) -> i32 {
+    if paths.is_empty() {
+        eprintln!("Usage: bastion_character_sheet --validate <character.json>...");
+        return 2;
+    }
+
+    let mut defs = origins::builtin_origins();
+    origins::register(&mut defs, app::load_custom_origins());
+
+    let mut code = 0;
+    for path in paths {
+        let character = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| model::character_from_json(&json));
+        match character {
+            Ok(character) => {
+                let issues = validation::validate(&character, &defs);
+                println!("{}", validation::report(path, &issues));
+                if validation::has_errors(&issues) {
+                    code = code.max(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("{}: could not load: {}", path, e);
+                code = 2;
+            }
+        }
+    }
+    code
+}
+
 fn theme(state: &CharacterSheet) -> Theme {
     let mut palette = Theme::Dark.palette();
     let mut custom = false;
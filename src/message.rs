@@ -1,4 +1,4 @@
-use crate::model::Origin;
+use crate::model::{DurationKind, Origin, StatTarget, WoundHealing};
 use iced::widget::text_editor;
 use std::path::PathBuf;
 
@@ -8,9 +8,8 @@ pub enum Message {
     LevelChanged(String),
     OriginSelected(Origin),
     AttributeChanged(AttributeField, i32),
-    TenderChanged(String),
     ArmorBonusChanged(String),
-    DrChanged(String),
+    DrQualifierChanged(String),
     BgColorChanged(String),
     FgColorChanged(String),
     AccentColorChanged(String),
@@ -19,22 +18,95 @@ pub enum Message {
     HpModifierChanged(String),
     ApplyHpModifier(i32),
 
-    WoundsChanged(i32),
+    WoundInputChanged(String),
+    WoundHealingSelected(WoundHealing),
+    AddWound,
+    TreatWound(usize),
+    HealWound(usize),
 
     SpellsInputChanged(String),
     MiraclesInputChanged(String),
     AdjustSpells(i32),
     AdjustMiracles(i32),
+    TakeRest(usize),
+    ToggleEditConditions,
+    AddCondition,
+    ExpireCondition(usize),
+    ConditionNameChanged(usize, String),
+    ConditionNotesChanged(usize, String),
+    ConditionDurationSelected(usize, DurationKind),
+    ConditionRoundsChanged(usize, u32),
+    AdvanceConditions,
+    StartEncounter,
+    EndEncounter,
+    NextRound,
+    UseAbility(usize),
+    DamageInputChanged(String),
+    DamageNoteChanged(String),
+    TakeDamage,
+    DealDamage,
+    RestRulesLoaded(Vec<crate::rest::RestRule>),
 
     SaveCharacter,
     SaveAsCharacter,
     ToggleSaveMenu,
+    ToggleValidationReport,
     LoadCharacter,
     SaveFileSelected(Option<PathBuf>),
     LoadFileSelected(Option<PathBuf>),
+    CloseRequested(iced::window::Id),
+    SaveBeforeContinuing,
+    DiscardChanges,
+    CancelPendingAction,
+    ShowVersionHistory,
+    CloseVersionHistory,
+    VersionSelected(usize),
+    RestoreVersion,
+    Autosave,
+    RestoreRecovery,
+    DiscardRecovery,
     DismissError,
+    DismissSalvageReport,
     DismissNotification,
-    InventoryAction(usize, text_editor::Action),
+    ToggleEditInventory,
+    AddItem,
+    RemoveItem(usize),
+    ItemChanged(usize, ItemField, String),
+    ToggleItemWeapon(usize, bool),
+    ToggleItemArmour(usize, bool),
+    ToggleArmourSlow(usize, bool),
+    ToggleItemEquipped(usize, bool),
+    SellItem(usize),
+    BuyNameChanged(String),
+    BuyPriceChanged(String),
+    BuyKindSelected(PurchaseKind),
+    BuyItem,
+    LedgerAmountChanged(String),
+    LedgerReasonChanged(String),
+    AddLedgerEntry,
+    XpInputChanged(String),
+    AddXp,
+    XpThresholdsChanged(String),
+    StartLevelUp,
+    LevelUpAttributeChanged(AttributeField, bool),
+    ConfirmLevelUp,
+    CancelLevelUp,
+    StartCreation,
+    BeginCreation,
+    CancelCreation,
+    CreationBack,
+    CreationNext,
+    CreationFinish,
+    CreationNameChanged(String),
+    CreationOriginSelected(Origin),
+    CreationAttributeChanged(AttributeField, i32),
+    CreationBudgetChanged(String),
+    CreationTenderChanged(String),
+    CreationItemInputChanged(String),
+    CreationAddItem,
+    CreationRemoveItem(usize),
+    CreationAbilityToggled(crate::model::Ability, bool),
+    RollWeaponDamage(usize),
     AddAbility,
     RequestDeleteAbility(usize),
     ConfirmDeleteAbility,
@@ -44,27 +116,199 @@ pub enum Message {
     AbilityBodyChanged(usize, text_editor::Action),
     AbilityDescChanged(usize, text_editor::Action),
     ToggleAbilityPrepared(usize, bool),
+    StartLoadoutSelection,
+    CancelLoadoutSelection,
+    LoadoutSelectionToggled(usize, bool),
+    PrepareSelection,
+    LoadoutNameChanged(String),
+    SaveLoadout,
+    LoadoutSelected(String),
+    DeleteLoadout(String),
     MoveAbilityUp(usize),
     MoveAbilityDown(usize),
     ToggleEditor,
-    OffsetChanged(OffsetField, String),
+    Undo,
+    Redo,
+    AddModifier(ModifierOwner),
+    RemoveModifier(ModifierOwner, usize),
+    ModifierSourceChanged(ModifierOwner, usize, String),
+    ModifierTargetSelected(ModifierOwner, usize, StatTarget),
+    ModifierValueChanged(ModifierOwner, usize, i32),
+    ToggleModifier(ModifierOwner, usize, bool),
     ToggleAbilityBrowser,
     ToggleEditAbilities,
     AbilityBrowserSearchChanged(String),
     AbilityBrowserTagToggled(String),
     ImportAbility(crate::model::Ability),
     AbilitiesLoaded(Vec<crate::model::Ability>),
+    OriginsLoaded(Vec<crate::origins::OriginDef>),
+    DiceExpressionChanged(String),
+    RollDice,
+    RollExpression(String),
+    RollAttributeCheck(AttributeField),
+    RequestClearRollLog,
+    ConfirmClearRollLog,
+    CancelClearRollLog,
+    ExportRollLog,
+    RollLogExportSelected(Option<PathBuf>),
 }
 
+/// The text field a message edits. Keystrokes in the same field share an
+/// undo step.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum OffsetField {
-    MaxHp,
-    Speed,
-    MaxInventorySlots,
-    MaxAbilities,
-    MaxSpells,
-    MaxMiracles,
-    CritRange,
+pub struct EditGroup {
+    message: std::mem::Discriminant<Message>,
+    target: EditTarget,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EditTarget {
+    Sheet,
+    Index(usize),
+    Item(usize, ItemField),
+    Modifier(ModifierOwner, usize),
+}
+
+impl Message {
+    /// Messages that only change what the sheet shows or holds in its input
+    /// fields, never the character, so they need no undo snapshot.
+    pub fn is_ui_only(&self) -> bool {
+        matches!(
+            self,
+            Message::ToggleEditor
+                | Message::ToggleSaveMenu
+                | Message::ToggleValidationReport
+                | Message::ToggleEditConditions
+                | Message::ToggleEditInventory
+                | Message::ToggleEditAbilities
+                | Message::ToggleAbilityBrowser
+                | Message::AbilityBrowserSearchChanged(_)
+                | Message::AbilityBrowserTagToggled(_)
+                | Message::WoundInputChanged(_)
+                | Message::WoundHealingSelected(_)
+                | Message::DamageInputChanged(_)
+                | Message::DamageNoteChanged(_)
+                | Message::BuyNameChanged(_)
+                | Message::BuyPriceChanged(_)
+                | Message::BuyKindSelected(_)
+                | Message::LedgerAmountChanged(_)
+                | Message::LedgerReasonChanged(_)
+                | Message::XpInputChanged(_)
+                | Message::LoadoutNameChanged(_)
+                | Message::DiceExpressionChanged(_)
+                | Message::LevelUpAttributeChanged(..)
+                | Message::CancelLevelUp
+                | Message::BeginCreation
+                | Message::CancelCreation
+                | Message::CreationBack
+                | Message::CreationNext
+                | Message::CreationNameChanged(_)
+                | Message::CreationOriginSelected(_)
+                | Message::CreationAttributeChanged(..)
+                | Message::CreationBudgetChanged(_)
+                | Message::CreationTenderChanged(_)
+                | Message::CreationItemInputChanged(_)
+                | Message::CreationAddItem
+                | Message::CreationRemoveItem(_)
+                | Message::CreationAbilityToggled(..)
+                | Message::RequestDeleteAbility(_)
+                | Message::CancelDeleteAbility
+                | Message::CancelLoadoutSelection
+                | Message::LoadoutSelectionToggled(..)
+                | Message::RequestClearRollLog
+                | Message::CancelClearRollLog
+                | Message::ShowVersionHistory
+                | Message::CloseVersionHistory
+                | Message::VersionSelected(_)
+                | Message::CancelPendingAction
+                | Message::DismissError
+                | Message::DismissNotification
+                | Message::DismissSalvageReport
+                | Message::Autosave
+        )
+    }
+
+    pub fn edit_group(&self) -> Option<EditGroup> {
+        let target = match self {
+            Message::NameChanged(_)
+            | Message::LevelChanged(_)
+            | Message::ArmorBonusChanged(_)
+            | Message::DrQualifierChanged(_)
+            | Message::BgColorChanged(_)
+            | Message::FgColorChanged(_)
+            | Message::AccentColorChanged(_)
+            | Message::HpInputChanged(_)
+            | Message::SpellsInputChanged(_)
+            | Message::MiraclesInputChanged(_)
+            | Message::XpThresholdsChanged(_) => EditTarget::Sheet,
+            Message::ConditionNameChanged(i, _)
+            | Message::ConditionNotesChanged(i, _)
+            | Message::AbilityNameChanged(i, _)
+            | Message::AbilityTagsChanged(i, _)
+            | Message::AbilityBodyChanged(i, _)
+            | Message::AbilityDescChanged(i, _) => EditTarget::Index(*i),
+            Message::ItemChanged(i, field, _) => EditTarget::Item(*i, *field),
+            Message::ModifierSourceChanged(owner, i, _) => EditTarget::Modifier(*owner, *i),
+            _ => return None,
+        };
+        Some(EditGroup {
+            message: std::mem::discriminant(self),
+            target,
+        })
+    }
+}
+
+/// Where a modifier is attached: the character itself, or an inventory item,
+/// ability or condition (by index).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModifierOwner {
+    Character,
+    Item(usize),
+    Ability(usize),
+    Condition(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PurchaseKind {
+    Gear,
+    Weapon,
+    Armour,
+}
+
+impl PurchaseKind {
+    pub fn all() -> [PurchaseKind; 3] {
+        [PurchaseKind::Gear, PurchaseKind::Weapon, PurchaseKind::Armour]
+    }
+}
+
+impl std::fmt::Display for PurchaseKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PurchaseKind::Gear => "Gear",
+                PurchaseKind::Weapon => "Weapon",
+                PurchaseKind::Armour => "Armour",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemField {
+    Name,
+    Slots,
+    Quantity,
+    Price,
+    Tags,
+    Notes,
+    Damage,
+    HeavyStrength,
+    Properties,
+    ArmourAc,
+    ArmourDr,
+    ArmourStrength,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -77,3 +321,35 @@ pub enum AttributeField {
     Intelligence,
     Luck,
 }
+
+impl AttributeField {
+    pub fn all() -> [AttributeField; 7] {
+        [
+            AttributeField::Strength,
+            AttributeField::Dexterity,
+            AttributeField::Endurance,
+            AttributeField::Faith,
+            AttributeField::Will,
+            AttributeField::Intelligence,
+            AttributeField::Luck,
+        ]
+    }
+}
+
+impl std::fmt::Display for AttributeField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                AttributeField::Strength => "Strength",
+                AttributeField::Dexterity => "Dexterity",
+                AttributeField::Endurance => "Endurance",
+                AttributeField::Faith => "Faith",
+                AttributeField::Will => "Will",
+                AttributeField::Intelligence => "Intelligence",
+                AttributeField::Luck => "Luck",
+            }
+        )
+    }
+}
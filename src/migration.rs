@@ -0,0 +1,503 @@
+use crate::logic;
+use crate::model::{Item, LedgerEntry, Modifier, StatTarget, Wound};
+use serde_json::Value;
+
+/// Save format written by this version of the app. Bump it and add a step to
+/// [`STEPS`] whenever the saved layout changes.
+pub const CURRENT_VERSION: u32 = 5;
+
+/// Format 3 wound counts were toggled with four pips, so never went higher.
+const LEGACY_MAX_WOUNDS: i64 = 4;
+
+/// A legacy field a step could not convert. Steps leave the save unchanged
+/// when they fail.
+#[derive(Debug)]
+struct StepError {
+    /// JSON path of the field, e.g. `["inventory", "3"]`.
+    path: Vec<String>,
+    message: String,
+}
+
+impl StepError {
+    fn new(path: &[&str], message: String) -> Self {
+        Self {
+            path: path.iter().map(|s| s.to_string()).collect(),
+            message,
+        }
+    }
+
+    /// The path as shown to users, e.g. `inventory[3]`.
+    fn label(&self) -> String {
+        let mut label = String::new();
+        for segment in &self.path {
+            if segment.parse::<usize>().is_ok() {
+                label.push_str(&format!("[{}]", segment));
+            } else {
+                if !label.is_empty() {
+                    label.push('.');
+                }
+                label.push_str(segment);
+            }
+        }
+        label
+    }
+}
+
+type Step = fn(&mut Value) -> Result<(), StepError>;
+
+/// `STEPS[n]` upgrades a format `n` save to format `n + 1`.
+///
+/// Saves from before the `version` field are format 0, but may be in any of
+/// the layouts up to format 4, so each step checks for the old shape before
+/// changing anything.
+const STEPS: [(&str, Step); CURRENT_VERSION as usize] = [
+    ("structured inventory", inventory_items),
+    ("tender ledger", tender_ledger),
+    ("stat modifiers", offset_modifiers),
+    ("wound list", wound_list),
+    ("dr qualifier", dr_qualifier),
+];
+
+/// Reads the save format version, rejecting saves from a newer app.
+fn save_version(value: &Value) -> Result<u64, String> {
+    let Some(obj) = value.as_object() else {
+        return Err("Expected a character object.".to_string());
+    };
+    let version = match obj.get("version") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| format!("Invalid save format version {}.", v))?,
+    };
+    if version > CURRENT_VERSION as u64 {
+        return Err(format!(
+            "This character was saved by a newer version of Bastion Character Sheet \
+             (save format {}, this version reads up to {}). Update the app to open it.",
+            version, CURRENT_VERSION
+        ));
+    }
+    Ok(version)
+}
+
+/// Upgrades a saved character to [`CURRENT_VERSION`] in place.
+pub fn migrate(value: &mut Value) -> Result<(), String> {
+    let version = save_version(value)?;
+    for (from, (name, step)) in STEPS.iter().enumerate().skip(version as usize) {
+        step(value).map_err(|e| {
+            format!(
+                "Could not upgrade the save from format {} ({}): {}",
+                from, name, e.message
+            )
+        })?;
+    }
+    value["version"] = Value::from(CURRENT_VERSION);
+    Ok(())
+}
+
+/// Like [`migrate`], but a legacy field that cannot be converted is removed
+/// and its path added to `dropped` instead of failing the whole load. Saves
+/// from a newer app are still rejected.
+pub fn migrate_dropping(value: &mut Value, dropped: &mut Vec<String>) -> Result<(), String> {
+    if value.get("version").is_some_and(|v| !v.is_u64()) {
+        remove_path(value, &["version".to_string()]);
+        dropped.push("version".to_string());
+    }
+    let version = save_version(value)?;
+    for (_, step) in STEPS.iter().skip(version as usize) {
+        while let Err(e) = step(value) {
+            if !remove_path(value, &e.path) {
+                return Err(e.message);
+            }
+            dropped.push(e.label());
+        }
+    }
+    value["version"] = Value::from(CURRENT_VERSION);
+    Ok(())
+}
+
+/// Removes the field or list entry at `path`, returning whether it existed.
+fn remove_path(value: &mut Value, path: &[String]) -> bool {
+    let Some((last, parent)) = path.split_last() else {
+        return false;
+    };
+    let parent = parent.iter().try_fold(value, |v, key| match v {
+        Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get_mut(i)),
+        other => other.get_mut(key),
+    });
+    match parent {
+        Some(Value::Object(map)) => map.remove(last).is_some(),
+        Some(Value::Array(items)) => match last.parse::<usize>() {
+            Ok(i) if i < items.len() => {
+                items.remove(i);
+                true
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Format 0 stored the inventory as one free-text string per slot.
+fn inventory_items(value: &mut Value) -> Result<(), StepError> {
+    let Some(inventory) = value.get_mut("inventory").and_then(|v| v.as_array_mut()) else {
+        return Ok(());
+    };
+    let mut migrated = Vec::new();
+    for (i, entry) in inventory.iter().enumerate() {
+        match entry {
+            Value::String(name) if name.trim().is_empty() => {}
+            Value::String(name) => {
+                migrated.push(serde_json::to_value(Item::new(name.trim())).unwrap_or_default())
+            }
+            Value::Object(_) => migrated.push(entry.clone()),
+            other => {
+                return Err(StepError::new(
+                    &["inventory", &i.to_string()],
+                    format!("inventory entry {} is not an item", other),
+                ));
+            }
+        }
+    }
+    *inventory = migrated;
+    Ok(())
+}
+
+/// Format 1 stored tender as a bare number; it becomes the opening balance.
+fn tender_ledger(value: &mut Value) -> Result<(), StepError> {
+    let Some(obj) = value.as_object_mut() else {
+        return Ok(());
+    };
+    let Some(tender) = obj.get("tender") else {
+        return Ok(());
+    };
+    if obj.contains_key("ledger") {
+        obj.remove("tender");
+        return Ok(());
+    }
+    let amount = tender
+        .as_i64()
+        .and_then(|n| i32::try_from(n).ok())
+        .ok_or_else(|| StepError::new(&["tender"], format!("tender {} is not a number", tender)))?;
+    obj.remove("tender");
+    let ledger = if amount != 0 {
+        vec![LedgerEntry::new(amount, "Opening balance", None)]
+    } else {
+        Vec::new()
+    };
+    obj.insert(
+        "ledger".to_string(),
+        serde_json::to_value(ledger).unwrap_or_default(),
+    );
+    Ok(())
+}
+
+/// Format 2 had one anonymous offset per derived stat; each non-zero offset
+/// becomes a "Legacy override" modifier.
+fn offset_modifiers(value: &mut Value) -> Result<(), StepError> {
+    let Some(obj) = value.as_object_mut() else {
+        return Ok(());
+    };
+    let offsets = [
+        ("max_hp_offset", StatTarget::MaxHp),
+        ("speed_offset", StatTarget::Speed),
+        ("max_inventory_slots_offset", StatTarget::CarryingSlots),
+        ("max_abilities_offset", StatTarget::PreparedAbilities),
+        ("max_spells_offset", StatTarget::SpellSlots),
+        ("max_miracles_offset", StatTarget::MiracleSlots),
+        ("crit_range_offset", StatTarget::CritRange),
+    ];
+    let mut migrated = Vec::new();
+    for (key, target) in offsets {
+        let Some(offset) = obj.get(key) else {
+            continue;
+        };
+        let amount = offset
+            .as_i64()
+            .and_then(|n| i32::try_from(n).ok())
+            .ok_or_else(|| StepError::new(&[key], format!("{} {} is not a number", key, offset)))?;
+        if amount != 0 {
+            migrated.push(Modifier::new("Legacy override", target, amount));
+        }
+    }
+    if !migrated.is_empty() && obj.get("modifiers").is_some_and(|m| !m.is_array()) {
+        return Err(StepError::new(
+            &["modifiers"],
+            "modifiers is not a list".to_string(),
+        ));
+    }
+
+    for (key, _) in offsets {
+        obj.remove(key);
+    }
+    if migrated.is_empty() {
+        return Ok(());
+    }
+    if let Value::Array(list) = obj
+        .entry("modifiers")
+        .or_insert_with(|| Value::Array(Vec::new()))
+    {
+        list.extend(migrated.iter().filter_map(|m| serde_json::to_value(m).ok()));
+    }
+    Ok(())
+}
+
+/// Format 3 counted wounds as a number; each becomes an undescribed wound
+/// that heals with rest.
+fn wound_list(value: &mut Value) -> Result<(), StepError> {
+    let Some(wounds) = value.get_mut("wounds") else {
+        return Ok(());
+    };
+    if let Some(count) = wounds.as_i64() {
+        let migrated = vec![
+            Wound {
+                description: "Wound".to_string(),
+                ..Wound::default()
+            };
+            count.clamp(0, LEGACY_MAX_WOUNDS) as usize
+        ];
+        *wounds = serde_json::to_value(migrated).unwrap_or_default();
+    } else if !wounds.is_array() {
+        return Err(StepError::new(
+            &["wounds"],
+            format!("wounds {} is neither a count nor a list", wounds),
+        ));
+    }
+    Ok(())
+}
+
+/// Format 4 stored DR as free text such as `5/holy`. DR now comes from
+/// equipped armour, so only the damage type suffix is kept.
+fn dr_qualifier(value: &mut Value) -> Result<(), StepError> {
+    let Some(obj) = value.as_object_mut() else {
+        return Ok(());
+    };
+    let Some(dr) = obj.get("dr") else {
+        return Ok(());
+    };
+    let Some(text) = dr.as_str() else {
+        return Err(StepError::new(&["dr"], format!("dr {} is not text", dr)));
+    };
+    let suffix = text.trim_start_matches(|c: char| c.is_ascii_digit() || c.is_whitespace());
+    let qualifier = match suffix.trim() {
+        "" => String::new(),
+        kind if kind.starts_with('/') => kind.to_string(),
+        kind => format!("/{}", kind),
+    };
+    if !logic::is_valid_dr_qualifier(&qualifier) {
+        return Err(StepError::new(
+            &["dr"],
+            format!("dr {} is not a number with a damage type", dr),
+        ));
+    }
+    obj.remove("dr");
+    obj.entry("dr_qualifier")
+        .or_insert(Value::String(qualifier));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Character, character_from_json};
+
+    /// A current save with the version field removed, as written before
+    /// versioning existed.
+    fn unversioned() -> Value {
+        let mut value = serde_json::to_value(Character::default()).unwrap();
+        value.as_object_mut().unwrap().remove("version");
+        value
+    }
+
+    #[test]
+    fn test_legacy_inventory_migration() {
+        let mut value = unversioned();
+        value["inventory"] = serde_json::json!(["Rope", "", "  Lantern \n", ""]);
+
+        let char = character_from_json(&value.to_string()).expect("Should migrate");
+        assert_eq!(char.inventory.len(), 2);
+        assert_eq!(char.inventory[0].name, "Rope");
+        assert_eq!(char.inventory[0].slots, 1);
+        assert_eq!(char.inventory[1].name, "Lantern");
+    }
+
+    #[test]
+    fn test_legacy_tender_migration() {
+        let mut value = unversioned();
+        let obj = value.as_object_mut().unwrap();
+        obj.remove("ledger");
+        obj.insert("tender".to_string(), serde_json::json!(135));
+
+        let char = character_from_json(&value.to_string()).expect("Should migrate");
+        assert_eq!(char.ledger.len(), 1);
+        assert_eq!(char.ledger[0].amount, 135);
+        assert_eq!(char.ledger[0].reason, "Opening balance");
+    }
+
+    #[test]
+    fn test_legacy_numbers_out_of_range_are_rejected() {
+        let mut value = unversioned();
+        value.as_object_mut().unwrap().remove("ledger");
+        value["tender"] = serde_json::json!(i64::from(i32::MAX) + 1);
+        let mut dropped = Vec::new();
+        migrate_dropping(&mut value, &mut dropped).expect("Should migrate");
+        assert_eq!(dropped, vec!["tender"]);
+
+        let mut value = unversioned();
+        value["max_hp_offset"] = serde_json::json!(4_294_967_298i64);
+        let err = character_from_json(&value.to_string()).unwrap_err();
+        assert!(err.contains("max_hp_offset 4294967298 is not a number"));
+    }
+
+    #[test]
+    fn test_legacy_wound_migration() {
+        let mut value = unversioned();
+        let obj = value.as_object_mut().unwrap();
+        obj.insert("wounds".to_string(), serde_json::json!(2));
+
+        let char = character_from_json(&value.to_string()).expect("Should migrate");
+        assert_eq!(char.wounds.len(), 2);
+        assert_eq!(char.wounds[0].description, "Wound");
+        assert_eq!(char.wounds[0].taken_at, 0);
+        assert!(char.wounds[0].heals_on_rest());
+    }
+
+    #[test]
+    fn test_legacy_wound_count_is_capped() {
+        let mut value = unversioned();
+        value["wounds"] = serde_json::json!(1_000_000_000_000_000i64);
+
+        let char = character_from_json(&value.to_string()).expect("Should migrate");
+        assert_eq!(char.wounds.len(), LEGACY_MAX_WOUNDS as usize);
+    }
+
+    #[test]
+    fn test_legacy_offset_migration() {
+        let mut value = unversioned();
+        let obj = value.as_object_mut().unwrap();
+        obj.remove("modifiers");
+        obj.insert("max_hp_offset".to_string(), serde_json::json!(2));
+        obj.insert("speed_offset".to_string(), serde_json::json!(0));
+        obj.insert("crit_range_offset".to_string(), serde_json::json!(-1));
+
+        let char = character_from_json(&value.to_string()).expect("Should migrate");
+        assert_eq!(char.modifiers.len(), 2);
+        assert_eq!(char.modifiers[0].source, "Legacy override");
+        assert_eq!(char.modifiers[0].target, StatTarget::MaxHp);
+        assert_eq!(char.modifiers[0].value, 2);
+        assert!(char.modifiers[0].active);
+        assert_eq!(char.modifiers[1].target, StatTarget::CritRange);
+        assert_eq!(char.modifiers[1].value, -1);
+    }
+
+    #[test]
+    fn test_legacy_dr_keeps_damage_type() {
+        for (dr, qualifier) in [
+            ("5/holy", "/holy"),
+            ("3", ""),
+            ("2 fire", "/fire"),
+            ("", ""),
+        ] {
+            let mut value = unversioned();
+            let obj = value.as_object_mut().unwrap();
+            obj.remove("dr_qualifier");
+            obj.insert("dr".to_string(), serde_json::json!(dr));
+
+            let char = character_from_json(&value.to_string()).expect("Should migrate");
+            assert_eq!(char.dr_qualifier, qualifier, "from {:?}", dr);
+        }
+
+        let mut value = unversioned();
+        value["dr"] = serde_json::json!("see notes (2)");
+        let err = character_from_json(&value.to_string()).unwrap_err();
+        assert!(err.contains("dr qualifier"));
+    }
+
+    #[test]
+    fn test_fixture_free_text_inventory() {
+        let char = character_from_json(include_str!("../tests/fixtures/v0_free_text_inventory.json"))
+            .expect("Should migrate");
+        assert_eq!(char.version, CURRENT_VERSION);
+        assert_eq!(char.name, "Brannoc");
+        assert_eq!(
+            char.inventory.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(),
+            vec!["Longsword", "Rope (50 ft)", "Torch"]
+        );
+        assert_eq!(crate::logic::calculate_tender(&char), 42);
+        assert_eq!(char.modifiers.len(), 2);
+        assert_eq!(char.modifiers[0].target, StatTarget::MaxHp);
+        assert_eq!(char.modifiers[1].target, StatTarget::Speed);
+        assert_eq!(char.wounds.len(), 1);
+        assert!(char.roll_log.is_empty());
+    }
+
+    #[test]
+    fn test_fixture_structured_items() {
+        let char = character_from_json(include_str!("../tests/fixtures/v0_structured_items.json"))
+            .expect("Should migrate");
+        assert_eq!(char.inventory.len(), 2);
+        assert!(char.inventory[0].weapon.is_some());
+        assert!(char.inventory[1].armour.is_some());
+        assert_eq!(crate::logic::calculate_tender(&char), 80);
+        assert_eq!(char.roll_log.len(), 1);
+        assert!(char.modifiers.is_empty());
+    }
+
+    #[test]
+    fn test_fixture_tender_ledger() {
+        let char = character_from_json(include_str!("../tests/fixtures/v0_tender_ledger.json"))
+            .expect("Should migrate");
+        assert_eq!(char.ledger.len(), 2);
+        assert_eq!(crate::logic::calculate_tender(&char), 150);
+        assert_eq!(char.level_history.len(), 1);
+        assert_eq!(char.modifiers.len(), 1);
+        assert_eq!(char.modifiers[0].target, StatTarget::CritRange);
+        assert_eq!(char.wounds.len(), 2);
+    }
+
+    #[test]
+    fn test_fixture_modifiers() {
+        let char = character_from_json(include_str!("../tests/fixtures/v0_modifiers.json"))
+            .expect("Should migrate");
+        assert_eq!(char.modifiers.len(), 1);
+        assert_eq!(char.modifiers[0].source, "Ring of Vigor");
+        assert_eq!(char.conditions.len(), 1);
+        assert_eq!(char.loadouts.len(), 1);
+        assert_eq!(char.wounds.len(), 3);
+        assert_eq!(char.wounds[2].description, "Wound");
+    }
+
+    #[test]
+    fn test_fixture_current_format() {
+        let content = include_str!("../tests/fixtures/v4_current.json");
+        let char = character_from_json(content).expect("Should load");
+        assert_eq!(char.wounds.len(), 1);
+        assert_eq!(char.wounds[0].description, "Broken arm");
+
+        // Saving and loading again changes nothing.
+        let saved = serde_json::to_string(&char).unwrap();
+        let reloaded = character_from_json(&saved).unwrap();
+        assert_eq!(serde_json::to_value(&char).unwrap(), serde_json::to_value(&reloaded).unwrap());
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let mut value = serde_json::to_value(Character::default()).unwrap();
+        value["version"] = serde_json::json!(CURRENT_VERSION + 1);
+
+        let err = character_from_json(&value.to_string()).unwrap_err();
+        assert!(err.contains("newer version"));
+        assert!(err.contains(&format!("save format {}", CURRENT_VERSION + 1)));
+    }
+
+    #[test]
+    fn test_migration_errors() {
+        let mut value = unversioned();
+        value["tender"] = serde_json::json!("lots");
+        value.as_object_mut().unwrap().remove("ledger");
+        let err = character_from_json(&value.to_string()).unwrap_err();
+        assert!(err.starts_with("Could not upgrade the save from format 1 (tender ledger)"));
+
+        assert!(character_from_json("[]").unwrap_err().contains("character object"));
+        assert!(character_from_json("{").unwrap_err().starts_with("Not valid JSON"));
+    }
+}
@@ -1,60 +1,39 @@
+use crate::dice::{DieRoll, RollResult};
+use crate::encounter::Encounter;
+use crate::message::AttributeField;
+use crate::migration;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TextSpan {
     pub content: String,
     pub bold: bool,
     pub italic: bool,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
-pub enum Origin {
-    #[default]
-    Human,
-    Elf,
-    HalfElf,
-    Dwarf,
-    Orc,
-    Gnome,
-    Halfling,
-    GiantKin,
-}
+/// Identifier of an origin definition, e.g. `"GiantKin"`. The rules and
+/// display name live in its [`OriginDef`](crate::origins::OriginDef).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Origin(String);
 
 impl Origin {
-    pub fn all() -> [Origin; 8] {
-        [
-            Origin::Human,
-            Origin::Elf,
-            Origin::HalfElf,
-            Origin::Dwarf,
-            Origin::Orc,
-            Origin::Gnome,
-            Origin::Halfling,
-            Origin::GiantKin,
-        ]
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    pub fn id(&self) -> &str {
+        &self.0
     }
 }
 
-impl std::fmt::Display for Origin {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Origin::Human => "Human",
-                Origin::Elf => "Elf",
-                Origin::HalfElf => "Half-Elf",
-                Origin::Dwarf => "Dwarf",
-                Origin::Orc => "Orc",
-                Origin::Gnome => "Gnome",
-                Origin::Halfling => "Halfling",
-                Origin::GiantKin => "Giant-kin",
-            }
-        )
+impl Default for Origin {
+    fn default() -> Self {
+        Self::new("Human")
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Attributes {
     pub strength: i32,
     pub dexterity: i32,
@@ -79,7 +58,197 @@ impl Default for Attributes {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+impl Attributes {
+    pub fn get(&self, field: AttributeField) -> i32 {
+        match field {
+            AttributeField::Strength => self.strength,
+            AttributeField::Dexterity => self.dexterity,
+            AttributeField::Endurance => self.endurance,
+            AttributeField::Faith => self.faith,
+            AttributeField::Will => self.will,
+            AttributeField::Intelligence => self.intelligence,
+            AttributeField::Luck => self.luck,
+        }
+    }
+
+    pub fn get_mut(&mut self, field: AttributeField) -> &mut i32 {
+        match field {
+            AttributeField::Strength => &mut self.strength,
+            AttributeField::Dexterity => &mut self.dexterity,
+            AttributeField::Endurance => &mut self.endurance,
+            AttributeField::Faith => &mut self.faith,
+            AttributeField::Will => &mut self.will,
+            AttributeField::Intelligence => &mut self.intelligence,
+            AttributeField::Luck => &mut self.luck,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StatTarget {
+    MaxHp,
+    Speed,
+    CarryingSlots,
+    PreparedAbilities,
+    SpellSlots,
+    MiracleSlots,
+    CritRange,
+    ArmorClass,
+}
+
+impl StatTarget {
+    pub fn all() -> [StatTarget; 8] {
+        [
+            StatTarget::MaxHp,
+            StatTarget::Speed,
+            StatTarget::CarryingSlots,
+            StatTarget::PreparedAbilities,
+            StatTarget::SpellSlots,
+            StatTarget::MiracleSlots,
+            StatTarget::CritRange,
+            StatTarget::ArmorClass,
+        ]
+    }
+}
+
+impl std::fmt::Display for StatTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                StatTarget::MaxHp => "Max HP",
+                StatTarget::Speed => "Speed",
+                StatTarget::CarryingSlots => "Carrying Slots",
+                StatTarget::PreparedAbilities => "Prepared Abilities",
+                StatTarget::SpellSlots => "Spell Slots",
+                StatTarget::MiracleSlots => "Miracle Slots",
+                StatTarget::CritRange => "Crit Range",
+                StatTarget::ArmorClass => "AC",
+            }
+        )
+    }
+}
+
+/// A named adjustment to a derived stat, e.g. "Ring of Vigor +2 Max HP".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Modifier {
+    pub source: String,
+    pub target: StatTarget,
+    pub value: i32,
+    #[serde(default = "default_true")]
+    pub active: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Modifier {
+    pub fn new(source: impl Into<String>, target: StatTarget, value: i32) -> Self {
+        Self {
+            source: source.into(),
+            target,
+            value,
+            active: true,
+        }
+    }
+}
+
+impl std::fmt::Display for Modifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {:+} {}", self.source, self.value, self.target)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DurationKind {
+    Instantaneous,
+    Short,
+    Long,
+    Rounds,
+}
+
+impl DurationKind {
+    pub fn all() -> [DurationKind; 4] {
+        [
+            DurationKind::Instantaneous,
+            DurationKind::Short,
+            DurationKind::Long,
+            DurationKind::Rounds,
+        ]
+    }
+}
+
+impl std::fmt::Display for DurationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                DurationKind::Instantaneous => "Instantaneous",
+                DurationKind::Short => "Short",
+                DurationKind::Long => "Long",
+                DurationKind::Rounds => "Rounds",
+            }
+        )
+    }
+}
+
+/// How long a condition lasts: one of the ability duration categories, or a
+/// number of combat rounds remaining.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConditionDuration {
+    Instantaneous,
+    Short,
+    Long,
+    Rounds(u32),
+}
+
+impl ConditionDuration {
+    pub fn kind(self) -> DurationKind {
+        match self {
+            ConditionDuration::Instantaneous => DurationKind::Instantaneous,
+            ConditionDuration::Short => DurationKind::Short,
+            ConditionDuration::Long => DurationKind::Long,
+            ConditionDuration::Rounds(_) => DurationKind::Rounds,
+        }
+    }
+}
+
+impl std::fmt::Display for ConditionDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConditionDuration::Rounds(1) => write!(f, "1 round left"),
+            ConditionDuration::Rounds(n) => write!(f, "{} rounds left", n),
+            other => write!(f, "{}", other.kind()),
+        }
+    }
+}
+
+/// An active effect on the character, such as a Focus or a buff spell.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Condition {
+    pub name: String,
+    pub duration: ConditionDuration,
+    #[serde(default)]
+    pub modifiers: Vec<Modifier>,
+    #[serde(default)]
+    pub notes: String,
+}
+
+impl Condition {
+    pub fn new(name: impl Into<String>, duration: ConditionDuration) -> Self {
+        Self {
+            name: name.into(),
+            duration,
+            modifiers: Vec::new(),
+            notes: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct Ability {
     pub name: String,
     #[serde(default)]
@@ -89,45 +258,323 @@ pub struct Ability {
     pub tags: String,
     #[serde(default)]
     pub prepared: bool,
+    /// Applied while the ability is prepared.
+    #[serde(default)]
+    pub modifiers: Vec<Modifier>,
     #[serde(default, skip_serializing)]
     pub body_spans: Vec<TextSpan>,
     #[serde(default, skip_serializing)]
     pub desc_spans: Vec<TextSpan>,
 }
 
+impl Ability {
+    /// Whether the comma-separated tags include `tag` (case-insensitive).
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags
+            .split(',')
+            .any(|t| t.trim().eq_ignore_ascii_case(tag))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum WoundHealing {
+    /// Heals when a rest heals wounds.
+    #[default]
+    Rest,
+    /// Only heals on a rest once it has been treated.
+    Treatment,
+    /// Never heals on its own.
+    Permanent,
+}
+
+impl WoundHealing {
+    pub fn all() -> [WoundHealing; 3] {
+        [
+            WoundHealing::Rest,
+            WoundHealing::Treatment,
+            WoundHealing::Permanent,
+        ]
+    }
+}
+
+impl std::fmt::Display for WoundHealing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                WoundHealing::Rest => "Heals with rest",
+                WoundHealing::Treatment => "Needs treatment",
+                WoundHealing::Permanent => "Permanent",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Wound {
+    pub description: String,
+    /// Unix timestamp, or 0 when unknown (wounds migrated from old saves).
+    #[serde(default)]
+    pub taken_at: u64,
+    #[serde(default)]
+    pub healing: WoundHealing,
+    #[serde(default)]
+    pub treated: bool,
+}
+
+impl Wound {
+    pub fn new(description: impl Into<String>, healing: WoundHealing) -> Self {
+        Self {
+            description: description.into(),
+            taken_at: crate::timestamp::now(),
+            healing,
+            treated: false,
+        }
+    }
+
+    pub fn heals_on_rest(&self) -> bool {
+        match self.healing {
+            WoundHealing::Rest => true,
+            WoundHealing::Treatment => self.treated,
+            WoundHealing::Permanent => false,
+        }
+    }
+}
+
+/// A named set of abilities to prepare together, e.g. a dungeon kit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Loadout {
+    pub name: String,
+    pub abilities: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Item {
+    pub name: String,
+    /// Carrying slots taken up by the whole entry, regardless of quantity.
+    #[serde(default = "default_one")]
+    pub slots: i32,
+    #[serde(default = "default_one")]
+    pub quantity: i32,
+    /// Price in tender for a single unit.
+    #[serde(default)]
+    pub price: i32,
+    #[serde(default)]
+    pub tags: String,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub equipped: bool,
+    #[serde(default)]
+    pub weapon: Option<WeaponProfile>,
+    #[serde(default)]
+    pub armour: Option<ArmourProfile>,
+    /// Applied while the item is equipped.
+    #[serde(default)]
+    pub modifiers: Vec<Modifier>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeaponProfile {
+    /// Dice expression rolled for damage, e.g. `1d8+1`.
+    pub damage: String,
+    /// Strength needed to overcome the Heavy property, 0 when not Heavy.
+    #[serde(default)]
+    pub heavy: i32,
+    #[serde(default)]
+    pub properties: String,
+}
+
+impl Default for WeaponProfile {
+    fn default() -> Self {
+        Self {
+            damage: "1d6".to_string(),
+            heavy: 0,
+            properties: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ArmourProfile {
+    #[serde(default)]
+    pub ac: i32,
+    #[serde(default)]
+    pub dr: i32,
+    /// Strength required to wear the armour without being slowed.
+    #[serde(default)]
+    pub strength: i32,
+    #[serde(default)]
+    pub slow: bool,
+}
+
+fn default_one() -> i32 {
+    1
+}
+
+impl Item {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            slots: 1,
+            quantity: 1,
+            price: 0,
+            tags: String::new(),
+            notes: String::new(),
+            equipped: false,
+            weapon: None,
+            armour: None,
+            modifiers: Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TagFilterState {
     Include,
     Exclude,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RollLogEntry {
+    pub timestamp: u64,
+    pub label: String,
+    pub expression: String,
+    pub dice: Vec<DieRoll>,
+    pub total: i32,
+    #[serde(default)]
+    pub crit: bool,
+}
+
+impl RollLogEntry {
+    pub fn new(result: &RollResult, timestamp: u64) -> Self {
+        Self {
+            timestamp,
+            label: result.label.clone().unwrap_or_else(|| "Roll".to_string()),
+            expression: result.expression.clone(),
+            dice: result.dice.clone(),
+            total: result.total,
+            crit: result.crit,
+        }
+    }
+
+    pub fn dice_summary(&self) -> String {
+        self.dice
+            .iter()
+            .map(|d| {
+                let value = d.sign * d.value as i32;
+                if d.kept {
+                    value.to_string()
+                } else {
+                    format!("~{}", value)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+pub fn roll_log_to_csv(entries: &[RollLogEntry]) -> String {
+    let escape = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+    let mut csv = String::from("time,label,expression,dice,total,crit\n");
+    for entry in entries {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            crate::timestamp::format(entry.timestamp),
+            escape(&entry.label),
+            escape(&entry.expression),
+            escape(&entry.dice_summary()),
+            entry.total,
+            entry.crit
+        ));
+    }
+    csv
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub timestamp: u64,
+    /// Tender gained (positive) or spent (negative).
+    pub amount: i32,
+    pub reason: String,
+    #[serde(default)]
+    pub item: Option<String>,
+}
+
+impl LedgerEntry {
+    pub fn new(amount: i32, reason: impl Into<String>, item: Option<String>) -> Self {
+        Self {
+            timestamp: crate::timestamp::now(),
+            amount,
+            reason: reason.into(),
+            item,
+        }
+    }
+}
+
+/// A dated line in the session log, e.g. what a rest restored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionLogEntry {
+    pub timestamp: u64,
+    pub text: String,
+}
+
+impl SessionLogEntry {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            timestamp: crate::timestamp::now(),
+            text: text.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelUpRecord {
+    pub timestamp: u64,
+    pub from_level: i32,
+    pub to_level: i32,
+    pub max_hp_before: i32,
+    pub max_hp_after: i32,
+    pub prepared_slots_before: i32,
+    pub prepared_slots_after: i32,
+    #[serde(default)]
+    pub attribute_increases: Vec<String>,
+}
+
+/// Total XP needed to reach levels 2 through 10.
+pub fn default_xp_thresholds() -> Vec<i32> {
+    vec![10, 25, 45, 70, 100, 135, 175, 220, 270]
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Character {
+    /// Save format the character was written in; see `migration`.
+    #[serde(default)]
+    pub version: u32,
     pub name: String,
     pub level: i32,
     pub origin: Origin,
     pub attributes: Attributes,
     pub current_hp: i32,
-    pub wounds: i32,
-    pub xp: i32,
-    pub tender: i32,
-    pub armor_bonus: i32,
-    #[serde(default)]
-    pub max_hp_offset: i32,
-    #[serde(default)]
-    pub speed_offset: i32,
     #[serde(default)]
-    pub max_inventory_slots_offset: i32,
+    pub wounds: Vec<Wound>,
+    pub xp: i32,
+    #[serde(default = "default_xp_thresholds")]
+    pub xp_thresholds: Vec<i32>,
     #[serde(default)]
-    pub max_abilities_offset: i32,
+    pub level_history: Vec<LevelUpRecord>,
     #[serde(default)]
-    pub max_spells_offset: i32,
+    pub ledger: Vec<LedgerEntry>,
+    pub armor_bonus: i32,
     #[serde(default)]
-    pub max_miracles_offset: i32,
+    pub modifiers: Vec<Modifier>,
     #[serde(default)]
-    pub crit_range_offset: i32,
+    pub conditions: Vec<Condition>,
+    /// Damage type shown after the armour-derived DR, e.g. `/holy`. Empty
+    /// when the DR applies to all damage; see `logic::is_valid_dr_qualifier`.
     #[serde(default)]
-    pub dr: String,
+    pub dr_qualifier: String,
     #[serde(default)]
     pub background_color: String,
     #[serde(default)]
@@ -136,9 +583,17 @@ pub struct Character {
     pub accent_color: String,
     pub expended_spell_slots: i32,
     pub expended_miracle_slots: i32,
-    pub inventory: Vec<String>,
+    pub inventory: Vec<Item>,
     pub abilities: Vec<Ability>,
+    #[serde(default)]
+    pub loadouts: Vec<Loadout>,
     pub notes: String,
+    #[serde(default)]
+    pub roll_log: Vec<RollLogEntry>,
+    #[serde(default)]
+    pub session_log: Vec<SessionLogEntry>,
+    #[serde(default)]
+    pub encounter: Option<Encounter>,
 }
 
 impl Default for Character {
@@ -146,35 +601,45 @@ impl Default for Character {
         let attrs = Attributes::default();
         let origin = Origin::default();
         Self {
+            version: migration::CURRENT_VERSION,
             name: "New Character".to_string(),
             level: 1,
             origin,
             attributes: attrs,
             current_hp: 5,
-            wounds: 0,
+            wounds: Vec::new(),
             xp: 0,
-            tender: 200,
+            xp_thresholds: default_xp_thresholds(),
+            level_history: Vec::new(),
+            ledger: vec![LedgerEntry::new(200, "Starting tender", None)],
             armor_bonus: 0,
-            max_hp_offset: 0,
-            speed_offset: 0,
-            max_inventory_slots_offset: 0,
-            max_abilities_offset: 0,
-            max_spells_offset: 0,
-            max_miracles_offset: 0,
-            crit_range_offset: 0,
-            dr: String::new(),
+            modifiers: Vec::new(),
+            conditions: Vec::new(),
+            dr_qualifier: String::new(),
             background_color: String::new(),
             foreground_color: String::new(),
             accent_color: String::new(),
             expended_spell_slots: 0,
             expended_miracle_slots: 0,
-            inventory: vec![String::new(); 5],
+            inventory: Vec::new(),
             abilities: Vec::new(),
+            loadouts: Vec::new(),
             notes: String::new(),
+            roll_log: Vec::new(),
+            session_log: Vec::new(),
+            encounter: None,
         }
     }
 }
 
+/// Parses a saved character, upgrading older save formats along the way.
+pub fn character_from_json(content: &str) -> Result<Character, String> {
+    let mut value: serde_json::Value =
+        serde_json::from_str(content).map_err(|e| format!("Not valid JSON: {}", e))?;
+    migration::migrate(&mut value)?;
+    serde_json::from_value(value).map_err(|e| format!("Invalid character data: {}", e))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -190,4 +655,36 @@ mod tests {
         assert_eq!(char.attributes.strength, deserialized.attributes.strength);
         assert_eq!(char.origin, deserialized.origin);
     }
+
+    #[test]
+    fn test_load_save_without_roll_log() {
+        let mut value = serde_json::to_value(Character::default()).unwrap();
+        value.as_object_mut().unwrap().remove("roll_log");
+
+        let char: Character = serde_json::from_value(value).expect("Should deserialize");
+        assert!(char.roll_log.is_empty());
+    }
+
+    #[test]
+    fn test_roll_log_csv() {
+        let entry = RollLogEntry {
+            timestamp: 0,
+            label: "Strength check".to_string(),
+            expression: "1d12+2".to_string(),
+            dice: vec![DieRoll {
+                sides: 12,
+                value: 11,
+                sign: 1,
+                kept: true,
+            }],
+            total: 13,
+            crit: true,
+        };
+
+        let csv = roll_log_to_csv(&[entry]);
+        assert_eq!(
+            csv.lines().nth(1),
+            Some("1970-01-01 00:00,\"Strength check\",\"1d12+2\",\"11\",13,true")
+        );
+    }
 }
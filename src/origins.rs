@@ -0,0 +1,176 @@
+use crate::message::AttributeField;
+use crate::model::Origin;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const BUILTIN_ORIGINS: &str = include_str!("../resources/origins.json");
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OriginDef {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub traits: Vec<String>,
+    #[serde(default)]
+    pub rules: OriginRules,
+}
+
+impl std::fmt::Display for OriginDef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Formula modifiers applied by an origin. Every field defaults to the
+/// standard (Human) rules, so data files only list what differs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OriginRules {
+    pub hp_per_level: i32,
+    pub hp_per_endurance: i32,
+    pub carrying_slots_base: i32,
+    /// Added to Strength when overcoming the Heavy property of weapons.
+    pub heavy_strength_offset: i32,
+    /// Armour price as a percentage of the listed price.
+    pub armour_price_percent: i32,
+    pub ignores_armour_strength: bool,
+    pub ignores_slow_armour: bool,
+    /// Minimum starting values, keyed by attribute name (e.g. `"Luck": 3`).
+    pub starting_attributes: BTreeMap<String, i32>,
+}
+
+impl Default for OriginRules {
+    fn default() -> Self {
+        Self {
+            hp_per_level: 2,
+            hp_per_endurance: 3,
+            carrying_slots_base: 4,
+            heavy_strength_offset: 0,
+            armour_price_percent: 100,
+            ignores_armour_strength: false,
+            ignores_slow_armour: false,
+            starting_attributes: BTreeMap::new(),
+        }
+    }
+}
+
+impl OriginRules {
+    pub fn starting_attribute(&self, field: AttributeField) -> i32 {
+        self.starting_attributes
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&field.to_string()))
+            .map(|(_, value)| *value)
+            .unwrap_or(1)
+    }
+}
+
+static STANDARD_RULES: Lazy<OriginRules> = Lazy::new(OriginRules::default);
+
+pub fn builtin_origins() -> Vec<OriginDef> {
+    parse_origins(BUILTIN_ORIGINS).expect("Built-in origins should be valid")
+}
+
+/// Parses an origin data file holding either a single origin or a list of them.
+pub fn parse_origins(content: &str) -> Result<Vec<OriginDef>, serde_json::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OriginFile {
+        Many(Vec<OriginDef>),
+        One(OriginDef),
+    }
+
+    Ok(match serde_json::from_str(content)? {
+        OriginFile::Many(defs) => defs,
+        OriginFile::One(def) => vec![def],
+    })
+}
+
+/// Adds loaded origins to `defs`, replacing any with the same id.
+pub fn register(defs: &mut Vec<OriginDef>, loaded: Vec<OriginDef>) {
+    for def in loaded {
+        match defs.iter_mut().find(|d| d.id == def.id) {
+            Some(existing) => *existing = def,
+            None => defs.push(def),
+        }
+    }
+}
+
+pub fn find<'a>(defs: &'a [OriginDef], origin: &Origin) -> Option<&'a OriginDef> {
+    defs.iter().find(|d| d.id == origin.id())
+}
+
+/// Display name of an origin, or its id when it is not defined (e.g. a
+/// homebrew origin whose data file is missing).
+pub fn name<'a>(defs: &'a [OriginDef], origin: &'a Origin) -> &'a str {
+    find(defs, origin).map_or(origin.id(), |d| d.name.as_str())
+}
+
+/// Looks up an origin's rules, falling back to standard rules for unknown ids
+/// so characters with a missing homebrew origin still load.
+pub fn rules<'a>(defs: &'a [OriginDef], origin: &Origin) -> &'a OriginRules {
+    find(defs, origin).map_or(&STANDARD_RULES, |d| &d.rules)
+}
+
+pub fn traits<'a>(defs: &'a [OriginDef], origin: &Origin) -> &'a [String] {
+    find(defs, origin).map_or(&[], |d| d.traits.as_slice())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_origins() {
+        let defs = builtin_origins();
+        assert_eq!(defs.len(), 8);
+        assert!(defs.iter().all(|d| !d.traits.is_empty()));
+
+        let giant_kin = find(&defs, &Origin::new("GiantKin")).expect("Should be built in");
+        assert_eq!(giant_kin.name, "Giant-kin");
+        assert_eq!(giant_kin.rules.hp_per_level, 3);
+        assert_eq!(giant_kin.rules.armour_price_percent, 110);
+
+        let human = rules(&defs, &Origin::new("Human"));
+        assert_eq!(human.starting_attribute(AttributeField::Luck), 3);
+        assert_eq!(human.starting_attribute(AttributeField::Strength), 1);
+    }
+
+    #[test]
+    fn test_register_homebrew_origin() {
+        let loaded = parse_origins(
+            r#"{
+                "id": "Kobold",
+                "name": "Kobold",
+                "traits": ["Kobolds are small."],
+                "rules": { "heavy_strength_offset": -2, "carrying_slots_base": 3 }
+            }"#,
+        )
+        .expect("Should parse");
+        let mut defs = builtin_origins();
+        register(&mut defs, loaded);
+
+        let origin = Origin::new("Kobold");
+        assert_eq!(defs.len(), 9);
+        assert_eq!(name(&defs, &origin), "Kobold");
+        let rules = rules(&defs, &origin);
+        assert_eq!(rules.carrying_slots_base, 3);
+        assert_eq!(rules.hp_per_level, 2);
+
+        // Registering an existing id replaces it.
+        let mut elf = find(&defs, &Origin::new("Elf")).unwrap().clone();
+        elf.name = "High Elf".to_string();
+        register(&mut defs, vec![elf]);
+        assert_eq!(defs.len(), 9);
+        assert_eq!(name(&defs, &Origin::new("Elf")), "High Elf");
+    }
+
+    #[test]
+    fn test_unknown_origin_falls_back() {
+        let defs = builtin_origins();
+        let origin = Origin::new("Missing");
+        assert_eq!(name(&defs, &origin), "Missing");
+        assert_eq!(rules(&defs, &origin), &OriginRules::default());
+        assert!(traits(&defs, &origin).is_empty());
+    }
+}
@@ -251,6 +251,7 @@ pub fn parse_bastion_abilities(content: &str) -> Vec<Ability> {
             body_spans: body_spans,
             desc_spans: desc_spans,
             prepared: false,
+            modifiers: Vec::new(),
         });
     }
 
@@ -0,0 +1,95 @@
+use crate::backup;
+use crate::model::Character;
+use crate::timestamp;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// How often unsaved changes are written to the recovery file.
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Unsaved edits written periodically so they survive a crash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoverySnapshot {
+    /// Unix seconds when the snapshot was written.
+    pub saved_at: u64,
+    /// File the character was loaded from or last saved to, if any.
+    pub file_path: Option<PathBuf>,
+    pub character: Character,
+}
+
+impl RecoverySnapshot {
+    pub fn new(character: &Character, file_path: Option<PathBuf>) -> Self {
+        Self {
+            saved_at: timestamp::now(),
+            file_path,
+            character: character.clone(),
+        }
+    }
+
+    /// Whether the snapshot holds edits that the saved file doesn't, i.e. it
+    /// was written after the file was last modified.
+    pub fn is_newer_than_file(&self) -> bool {
+        match self.file_path.as_deref().and_then(modified_secs) {
+            Some(modified) => self.saved_at > modified,
+            None => true,
+        }
+    }
+}
+
+fn modified_secs(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs())
+}
+
+pub fn write(path: &Path, snapshot: &RecoverySnapshot) -> Result<(), String> {
+    let json = serde_json::to_string(snapshot).map_err(|e| e.to_string())?;
+    backup::write_atomic(path, &json)
+}
+
+/// Reads the snapshot at `path`, if there is one worth offering. Snapshots
+/// that are unreadable or older than their file are deleted.
+pub fn take_pending(path: &Path) -> Option<RecoverySnapshot> {
+    let content = fs::read_to_string(path).ok()?;
+    match serde_json::from_str::<RecoverySnapshot>(&content) {
+        Ok(snapshot) if snapshot.is_newer_than_file() => Some(snapshot),
+        _ => {
+            clear(path);
+            None
+        }
+    }
+}
+
+pub fn clear(path: &Path) {
+    let _ = fs::remove_file(path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_age() {
+        let dir = std::env::temp_dir().join(format!("bastion_recovery_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let sheet = dir.join("Aster.json");
+        fs::write(&sheet, "{}").unwrap();
+
+        let mut snapshot = RecoverySnapshot::new(&Character::default(), Some(sheet.clone()));
+        snapshot.saved_at = modified_secs(&sheet).unwrap() + 10;
+        assert!(snapshot.is_newer_than_file());
+
+        let recovery = dir.join("recovery.json");
+        snapshot.saved_at = 0;
+        write(&recovery, &snapshot).unwrap();
+        assert!(take_pending(&recovery).is_none());
+        assert!(!recovery.exists());
+
+        snapshot.file_path = None;
+        write(&recovery, &snapshot).unwrap();
+        assert!(take_pending(&recovery).is_some());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
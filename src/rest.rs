@@ -0,0 +1,209 @@
+use crate::dice;
+use crate::logic;
+use crate::model::{Character, DurationKind};
+use crate::origins::OriginDef;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+const BUILTIN_REST_RULES: &str = include_str!("../resources/rest.json");
+
+/// How much of a resource a rest restores, e.g. `"All"`, `{ "Amount": 2 }`
+/// or `{ "Roll": "1d6+1" }`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Recovery {
+    #[default]
+    None,
+    All,
+    Amount(i32),
+    Roll(String),
+}
+
+impl Recovery {
+    /// Amount restored when `missing` points are missing; never more than that.
+    fn resolve<R: Rng + ?Sized>(&self, missing: i32, rng: &mut R) -> i32 {
+        let amount = match self {
+            Recovery::None => 0,
+            Recovery::All => missing,
+            Recovery::Amount(n) => *n,
+            Recovery::Roll(expr) => dice::parse(expr).map(|e| e.roll(rng).total).unwrap_or(0),
+        };
+        amount.clamp(0, missing.max(0))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestRule {
+    pub name: String,
+    #[serde(default)]
+    pub hp: Recovery,
+    #[serde(default)]
+    pub spell_slots: Recovery,
+    #[serde(default)]
+    pub miracle_slots: Recovery,
+    /// Number of wounds healed.
+    #[serde(default)]
+    pub wounds: i32,
+    /// Conditions with these durations end when the rest is taken.
+    #[serde(default)]
+    pub ends_conditions: Vec<DurationKind>,
+}
+
+pub fn builtin_rest_rules() -> Vec<RestRule> {
+    parse_rest_rules(BUILTIN_REST_RULES).expect("Built-in rest rules should be valid")
+}
+
+/// Parses a list of rest rules, rejecting dice expressions that cannot roll.
+pub fn parse_rest_rules(content: &str) -> Result<Vec<RestRule>, String> {
+    let rules: Vec<RestRule> = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    for rule in &rules {
+        for recovery in [&rule.hp, &rule.spell_slots, &rule.miracle_slots] {
+            if let Recovery::Roll(expr) = recovery {
+                dice::parse(expr).map_err(|e| format!("{}: {}", rule.name, e))?;
+            }
+        }
+    }
+    Ok(rules)
+}
+
+fn plural(count: i32, noun: &str) -> String {
+    format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+}
+
+/// Applies a rest to the character and describes what was restored, e.g.
+/// `Long Rest: healed 1 wound, restored 6 HP, 2 spell slots`.
+pub fn apply_rest<R: Rng + ?Sized>(
+    char: &mut Character,
+    defs: &[OriginDef],
+    rule: &RestRule,
+    rng: &mut R,
+) -> String {
+    let mut restored = Vec::new();
+
+    // Effects end before anything is restored, so HP fills the maximum
+    // without their modifiers.
+    let ended = logic::end_conditions(char, &rule.ends_conditions);
+
+    // Wounds heal first so that recovered HP can fill the raised maximum.
+    let healed = logic::heal_wounds_on_rest(char, rule.wounds);
+    if !healed.is_empty() {
+        restored.push(format!(
+            "healed {} ({})",
+            plural(healed.len() as i32, "wound"),
+            healed.join(", ")
+        ));
+    }
+
+    let mut amounts = Vec::new();
+    let max_hp = logic::calculate_max_hp(char, defs);
+    char.current_hp = char.current_hp.min(max_hp);
+    let hp = rule.hp.resolve(max_hp - char.current_hp, rng);
+    if hp > 0 {
+        char.current_hp += hp;
+        amounts.push(format!("{} HP", hp));
+    }
+
+    let spells = rule.spell_slots.resolve(char.expended_spell_slots, rng);
+    if spells > 0 {
+        char.expended_spell_slots -= spells;
+        amounts.push(plural(spells, "spell slot"));
+    }
+
+    let miracles = rule.miracle_slots.resolve(char.expended_miracle_slots, rng);
+    if miracles > 0 {
+        char.expended_miracle_slots -= miracles;
+        amounts.push(plural(miracles, "miracle slot"));
+    }
+
+    if !amounts.is_empty() {
+        restored.push(format!("restored {}", amounts.join(", ")));
+    }
+
+    if !ended.is_empty() {
+        restored.push(format!("ended {}", ended.join(", ")));
+    }
+
+    if restored.is_empty() {
+        format!("{}: nothing to restore", rule.name)
+    } else {
+        format!("{}: {}", rule.name, restored.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Wound, WoundHealing};
+    use crate::origins::builtin_origins;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn long_rest() -> RestRule {
+        builtin_rest_rules()
+            .into_iter()
+            .find(|r| r.name == "Long Rest")
+            .expect("Long Rest should be built in")
+    }
+
+    #[test]
+    fn test_long_rest_restores_everything() {
+        let defs = builtin_origins();
+        let mut char = Character {
+            wounds: vec![
+                Wound::new("Cut", WoundHealing::Rest),
+                Wound::new("Lost eye", WoundHealing::Permanent),
+            ],
+            current_hp: 1,
+            expended_spell_slots: 1,
+            ..Character::default()
+        };
+        char.attributes.endurance = 2;
+
+        let summary = apply_rest(&mut char, &defs, &long_rest(), &mut StdRng::seed_from_u64(1));
+        assert_eq!(char.wounds.len(), 1);
+        assert_eq!(char.current_hp, logic::calculate_max_hp(&char, &defs));
+        assert_eq!(char.expended_spell_slots, 0);
+        assert_eq!(
+            summary,
+            "Long Rest: healed 1 wound (Cut), restored 5 HP, 1 spell slot"
+        );
+
+        let summary = apply_rest(
+            &mut Character::default(),
+            &defs,
+            &long_rest(),
+            &mut StdRng::seed_from_u64(1),
+        );
+        assert_eq!(summary, "Long Rest: nothing to restore");
+    }
+
+    #[test]
+    fn test_rolled_recovery_is_capped() {
+        let defs = builtin_origins();
+        let rule = RestRule {
+            name: "Breather".to_string(),
+            hp: Recovery::Roll("2d6+10".to_string()),
+            spell_slots: Recovery::Amount(1),
+            miracle_slots: Recovery::None,
+            wounds: 0,
+            ends_conditions: Vec::new(),
+        };
+        let mut char = Character {
+            current_hp: 3,
+            expended_spell_slots: 3,
+            expended_miracle_slots: 1,
+            ..Character::default()
+        };
+        apply_rest(&mut char, &defs, &rule, &mut StdRng::seed_from_u64(5));
+        assert_eq!(char.current_hp, logic::calculate_max_hp(&char, &defs));
+        assert_eq!(char.expended_spell_slots, 2);
+        assert_eq!(char.expended_miracle_slots, 1);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_dice() {
+        assert!(parse_rest_rules(r#"[{ "name": "Nap", "hp": { "Roll": "d" } }]"#).is_err());
+        let rules = parse_rest_rules(r#"[{ "name": "Nap", "hp": { "Amount": 2 } }]"#).unwrap();
+        assert_eq!(rules[0].hp, Recovery::Amount(2));
+        assert_eq!(rules[0].spell_slots, Recovery::None);
+    }
+}
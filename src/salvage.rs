@@ -0,0 +1,355 @@
+use crate::encounter::Encounter;
+use crate::migration;
+use crate::model::{
+    Ability, Character, Condition, Item, LedgerEntry, LevelUpRecord, Loadout,
+    Modifier, Origin, RollLogEntry, SessionLogEntry, Wound,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+/// A character rescued from a damaged save, with the parts that had to go.
+#[derive(Debug, Clone)]
+pub struct Salvaged {
+    pub character: Character,
+    /// JSON paths that could not be read, e.g. `inventory[2].weapon`.
+    pub dropped: Vec<String>,
+}
+
+type Check = fn(&Value) -> bool;
+
+fn fits<T: DeserializeOwned>(value: &Value) -> bool {
+    T::deserialize(value).is_ok()
+}
+
+/// How a field of a save is checked: as a whole, entry by entry, or (for
+/// objects whose fields share a type) field by field.
+enum Shape {
+    Value(Check),
+    List(Check),
+    Fields(Check),
+}
+
+/// Every field of `Character` with the type it must parse as. A test keeps
+/// this in step with the struct.
+const FIELDS: &[(&str, Shape)] = &[
+    ("version", Shape::Value(fits::<u32>)),
+    ("name", Shape::Value(fits::<String>)),
+    ("level", Shape::Value(fits::<i32>)),
+    ("origin", Shape::Value(fits::<Origin>)),
+    ("attributes", Shape::Fields(fits::<i32>)),
+    ("current_hp", Shape::Value(fits::<i32>)),
+    ("wounds", Shape::List(fits::<Wound>)),
+    ("xp", Shape::Value(fits::<i32>)),
+    ("xp_thresholds", Shape::List(fits::<i32>)),
+    ("level_history", Shape::List(fits::<LevelUpRecord>)),
+    ("ledger", Shape::List(fits::<LedgerEntry>)),
+    ("armor_bonus", Shape::Value(fits::<i32>)),
+    ("modifiers", Shape::List(fits::<Modifier>)),
+    ("conditions", Shape::List(fits::<Condition>)),
+    ("dr_qualifier", Shape::Value(fits::<String>)),
+    ("background_color", Shape::Value(fits::<String>)),
+    ("foreground_color", Shape::Value(fits::<String>)),
+    ("accent_color", Shape::Value(fits::<String>)),
+    ("expended_spell_slots", Shape::Value(fits::<i32>)),
+    ("expended_miracle_slots", Shape::Value(fits::<i32>)),
+    ("inventory", Shape::List(fits::<Item>)),
+    ("abilities", Shape::List(fits::<Ability>)),
+    ("loadouts", Shape::List(fits::<Loadout>)),
+    ("notes", Shape::Value(fits::<String>)),
+    ("roll_log", Shape::List(fits::<RollLogEntry>)),
+    ("session_log", Shape::List(fits::<SessionLogEntry>)),
+    ("encounter", Shape::Value(fits::<Option<Encounter>>)),
+];
+
+/// Loads as much of a damaged save as possible. Fields that fail to parse
+/// fall back to their defaults, list entries that fail are left out, and a
+/// file that is cut off or has a syntax error is read up to its last complete
+/// value before that point. Fails when nothing in the file looks like a
+/// character.
+pub fn salvage_character(content: &str) -> Result<Salvaged, String> {
+    let mut dropped = Vec::new();
+    let mut value = match serde_json::from_str::<Value>(content) {
+        Ok(value) => value,
+        Err(e) => {
+            let (value, cut) =
+                repair_truncated(content).ok_or_else(|| format!("Not valid JSON: {}", e))?;
+            dropped.push(format!("everything after byte {} (unreadable from there)", cut));
+            value
+        }
+    };
+    migration::migrate_dropping(&mut value, &mut dropped)?;
+    let Value::Object(fields) = value else {
+        return Err("Expected a character object.".to_string());
+    };
+
+    let defaults = match serde_json::to_value(Character::default()) {
+        Ok(Value::Object(map)) => map,
+        _ => return Err("Could not build a default character.".to_string()),
+    };
+    let mut doc = Map::new();
+    let mut kept = 0;
+    for (name, shape) in FIELDS {
+        let Some(input) = fields.get(*name) else {
+            continue;
+        };
+        if let Some(value) = salvage_field(name, shape, input, defaults.get(*name), &mut dropped)
+        {
+            doc.insert(name.to_string(), value);
+            if *name != "version" {
+                kept += 1;
+            }
+        }
+    }
+    if kept == 0 {
+        return Err("The file does not contain any character data.".to_string());
+    }
+
+    // Required fields that are missing or unreadable take their defaults;
+    // optional ones are left out so they get the same defaults as a normal load.
+    for (name, _) in FIELDS {
+        if doc.contains_key(*name) || !is_required(name, &defaults) {
+            continue;
+        }
+        if let Some(default) = defaults.get(*name) {
+            doc.insert(name.to_string(), default.clone());
+        }
+        if !fields.contains_key(*name) {
+            dropped.push(format!("{} (missing)", name));
+        }
+    }
+
+    let character = serde_json::from_value(Value::Object(doc)).map_err(|e| e.to_string())?;
+    Ok(Salvaged { character, dropped })
+}
+
+/// Whether a character fails to load without `name`. Checked against the
+/// small default character rather than the save.
+fn is_required(name: &str, defaults: &Map<String, Value>) -> bool {
+    let mut without = defaults.clone();
+    without.remove(name);
+    !fits::<Character>(&Value::Object(without))
+}
+
+/// Returns what can be kept of a field, or `None` when none of it can.
+fn salvage_field(
+    name: &str,
+    shape: &Shape,
+    input: &Value,
+    default: Option<&Value>,
+    dropped: &mut Vec<String>,
+) -> Option<Value> {
+    match (shape, input) {
+        (Shape::Value(check), _) if check(input) => Some(input.clone()),
+        (Shape::List(check), Value::Array(items)) => {
+            let mut kept = Vec::new();
+            for (i, item) in items.iter().enumerate() {
+                match salvage_entry(*check, item) {
+                    Some((entry, None)) => kept.push(entry),
+                    Some((entry, Some(field))) => {
+                        kept.push(entry);
+                        dropped.push(format!("{}[{}].{}", name, i, field));
+                    }
+                    None => dropped.push(format!("{}[{}]", name, i)),
+                }
+            }
+            Some(Value::Array(kept))
+        }
+        (Shape::Fields(check), Value::Object(input)) => {
+            let mut object = match default {
+                Some(Value::Object(default)) => default.clone(),
+                _ => Map::new(),
+            };
+            for (key, value) in object.iter_mut() {
+                match input.get(key) {
+                    Some(field) if check(field) => *value = field.clone(),
+                    Some(_) => dropped.push(format!("{}.{}", name, key)),
+                    None => dropped.push(format!("{}.{} (missing)", name, key)),
+                }
+            }
+            Some(Value::Object(object))
+        }
+        _ => {
+            dropped.push(name.to_string());
+            None
+        }
+    }
+}
+
+/// Checks a list entry, leaving out one broken field if that is what it
+/// takes. Returns the entry with the name of the field left out, if any.
+fn salvage_entry(check: Check, item: &Value) -> Option<(Value, Option<String>)> {
+    if check(item) {
+        return Some((item.clone(), None));
+    }
+    let Value::Object(fields) = item else {
+        return None;
+    };
+    fields.keys().find_map(|key| {
+        let mut trimmed = fields.clone();
+        trimmed.remove(key);
+        let trimmed = Value::Object(trimmed);
+        check(&trimmed).then(|| (trimmed, Some(key.clone())))
+    })
+}
+
+/// Closes a document after the last complete value before it ends or stops
+/// being valid JSON, returning it with the byte offset it was cut at.
+fn repair_truncated(content: &str) -> Option<(Value, usize)> {
+    let mut open = Vec::new();
+    let mut cuts = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in content.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => {
+                open.push(if c == '{' { '}' } else { ']' });
+                cuts.push((i + 1, open.clone()));
+            }
+            '}' | ']' => {
+                open.pop();
+                cuts.push((i + 1, open.clone()));
+            }
+            // Everything before a comma is a complete entry.
+            ',' => cuts.push((i, open.clone())),
+            _ => {}
+        }
+    }
+
+    let close = |(cut, open): &(usize, Vec<char>)| -> Option<Value> {
+        let mut text = content[..*cut].to_string();
+        text.extend(open.iter().rev());
+        serde_json::from_str(&text).ok()
+    };
+    // Every cut before the first syntax error closes into valid JSON and
+    // every cut after it does not, so the last good cut can be bisected.
+    let good = cuts.partition_point(|cut| close(cut).is_some());
+    let cut = cuts.get(good.checked_sub(1)?)?;
+    close(cut).map(|value| (value, cut.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Item;
+
+    fn saved() -> Character {
+        let mut char = Character {
+            name: "Aster".to_string(),
+            inventory: vec![Item::new("Rope"), Item::new("Torch")],
+            ..Character::default()
+        };
+        char.attributes.strength = 3;
+        char
+    }
+
+    #[test]
+    fn test_malformed_fields_fall_back() {
+        let mut value = serde_json::to_value(saved()).unwrap();
+        value["attributes"]["dexterity"] = serde_json::json!("high");
+        value["inventory"][0]["slots"] = serde_json::json!("one");
+        value["inventory"][1] = serde_json::json!(7);
+        value["level"] = serde_json::json!(null);
+        value.as_object_mut().unwrap().remove("notes");
+
+        let salvaged = salvage_character(&value.to_string()).expect("Should salvage");
+        let char = salvaged.character;
+        assert_eq!(char.name, "Aster");
+        assert_eq!(char.attributes.strength, 3);
+        assert_eq!(char.attributes.dexterity, 1);
+        assert_eq!(char.level, 1);
+        assert_eq!(char.inventory.len(), 1);
+        assert_eq!(char.inventory[0].name, "Rope");
+        assert_eq!(char.inventory[0].slots, 1);
+
+        let mut dropped = salvaged.dropped;
+        dropped.sort();
+        assert_eq!(
+            dropped,
+            vec![
+                "attributes.dexterity",
+                "inventory[0].slots",
+                "inventory[1]",
+                "level",
+                "notes (missing)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truncated_file() {
+        // Fields are written in declaration order, so abilities come late.
+        let content = serde_json::to_string_pretty(&saved()).unwrap();
+        let cut = content.find("\"abilities\"").unwrap() + 5;
+
+        let salvaged = salvage_character(&content[..cut]).expect("Should salvage");
+        assert_eq!(salvaged.character.name, "Aster");
+        assert_eq!(salvaged.character.inventory.len(), 2);
+        assert!(salvaged.dropped[0].contains("unreadable from there"));
+        assert!(salvaged.dropped.contains(&"abilities (missing)".to_string()));
+        // Optional fields are simply defaulted.
+        assert!(!salvaged.dropped.iter().any(|d| d.starts_with("roll_log")));
+    }
+
+    #[test]
+    fn test_syntax_error_mid_file() {
+        let mut content = serde_json::to_string_pretty(&saved()).unwrap();
+        let at = content.find("\"abilities\"").unwrap();
+        content.insert_str(at, "@@ ");
+
+        let salvaged = salvage_character(&content).expect("Should salvage");
+        assert_eq!(salvaged.character.name, "Aster");
+        assert_eq!(salvaged.character.inventory.len(), 2);
+        assert!(salvaged.dropped[0].starts_with("everything after byte"));
+        assert!(salvaged.dropped[0].contains("unreadable from there"));
+        assert!(salvaged.dropped.contains(&"abilities (missing)".to_string()));
+    }
+
+    #[test]
+    fn test_intact_file_drops_nothing() {
+        let salvaged = salvage_character(&serde_json::to_string(&saved()).unwrap()).unwrap();
+        assert!(salvaged.dropped.is_empty());
+        assert!(salvage_character("not json").is_err());
+    }
+
+    #[test]
+    fn test_unreadable_legacy_fields_are_dropped() {
+        let content = r#"{"name": "Aster", "tender": "lots", "inventory": ["Rope"],
+            "wounds": "two", "armor_bonus": 1, "speed_offset": "high"}"#;
+        let salvaged = salvage_character(content).expect("Should salvage");
+        assert_eq!(salvaged.character.name, "Aster");
+        assert_eq!(salvaged.character.inventory.len(), 1);
+        for path in ["tender", "wounds", "speed_offset"] {
+            assert!(salvaged.dropped.contains(&path.to_string()), "{}", path);
+        }
+
+        let newer = r#"{"version": 999, "name": "Aster"}"#;
+        assert!(salvage_character(newer).unwrap_err().contains("newer version"));
+    }
+
+    #[test]
+    fn test_nothing_to_salvage() {
+        assert!(salvage_character("{}").is_err());
+        assert!(salvage_character(r#"{"compilerOptions": {"strict": true}}"#).is_err());
+        assert!(salvage_character("[1, 2]").is_err());
+    }
+
+    #[test]
+    fn test_every_field_is_checked() {
+        let value = serde_json::to_value(Character::default()).unwrap();
+        let keys: Vec<&str> = value.as_object().unwrap().keys().map(|k| k.as_str()).collect();
+        let mut fields: Vec<&str> = FIELDS.iter().map(|(name, _)| *name).collect();
+        fields.sort();
+        assert_eq!(keys, fields);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::logic;
+use crate::message::AttributeField;
+use crate::model::Character;
+use crate::origins::OriginDef;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Severity::Warning => "warning",
+                Severity::Error => "error",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    LevelOutOfRange,
+    AttributeOutOfRange(AttributeField),
+    TooManyPrepared,
+    OverCarryingCapacity,
+    SpellSlotsOverspent,
+    MiracleSlotsOverspent,
+    WoundsExceedHp,
+    HpAboveMax,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub severity: Severity,
+    pub kind: IssueKind,
+    pub message: String,
+}
+
+impl Issue {
+    fn new(severity: Severity, kind: IssueKind, message: String) -> Self {
+        Self {
+            severity,
+            kind,
+            message,
+        }
+    }
+}
+
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// Checks a character against the rules, most severe issues first.
+pub fn validate(char: &Character, defs: &[OriginDef]) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    if !(1..=logic::MAX_LEVEL).contains(&char.level) {
+        issues.push(Issue::new(
+            Severity::Error,
+            IssueKind::LevelOutOfRange,
+            format!("Level {} is outside 1-{}.", char.level, logic::MAX_LEVEL),
+        ));
+    }
+
+    let attr_max = logic::max_attribute_value(char.level);
+    for field in AttributeField::all() {
+        let value = char.attributes.get(field);
+        if !(logic::MIN_ATTRIBUTE_VALUE..=attr_max).contains(&value) {
+            issues.push(Issue::new(
+                Severity::Warning,
+                IssueKind::AttributeOutOfRange(field),
+                format!(
+                    "{} is {} but must be between {} and {} at level {}.",
+                    field,
+                    value,
+                    logic::MIN_ATTRIBUTE_VALUE,
+                    attr_max,
+                    char.level
+                ),
+            ));
+        }
+    }
+
+    let prepared = char.abilities.iter().filter(|a| a.prepared).count() as i32;
+    let prepared_slots = logic::calculate_prepared_slots(char);
+    if prepared > prepared_slots {
+        issues.push(Issue::new(
+            Severity::Error,
+            IssueKind::TooManyPrepared,
+            format!(
+                "{} abilities prepared but only {} may be.",
+                prepared, prepared_slots
+            ),
+        ));
+    }
+
+    let used_slots = logic::calculate_used_slots(char);
+    let carrying_slots = logic::calculate_carrying_slots(char, defs);
+    if used_slots > carrying_slots {
+        issues.push(Issue::new(
+            Severity::Warning,
+            IssueKind::OverCarryingCapacity,
+            format!(
+                "Inventory uses {} slots but only {} can be carried.",
+                used_slots, carrying_slots
+            ),
+        ));
+    }
+
+    let spell_slots = logic::calculate_spell_slots(char);
+    if char.expended_spell_slots > spell_slots {
+        issues.push(Issue::new(
+            Severity::Error,
+            IssueKind::SpellSlotsOverspent,
+            format!(
+                "{} spell slots expended but only {} exist.",
+                char.expended_spell_slots, spell_slots
+            ),
+        ));
+    }
+
+    let miracle_slots = logic::calculate_miracle_slots(char);
+    if char.expended_miracle_slots > miracle_slots {
+        issues.push(Issue::new(
+            Severity::Error,
+            IssueKind::MiracleSlotsOverspent,
+            format!(
+                "{} miracle slots expended but only {} exist.",
+                char.expended_miracle_slots, miracle_slots
+            ),
+        ));
+    }
+
+    let unmodified_hp = logic::unmodified_max_hp(char, defs);
+    if !char.wounds.is_empty() && unmodified_hp < 1 {
+        issues.push(Issue::new(
+            Severity::Error,
+            IssueKind::WoundsExceedHp,
+            format!(
+                "Wounds push max HP below 1 ({} before the minimum applies).",
+                unmodified_hp
+            ),
+        ));
+    }
+    let max_hp = logic::max_hp_breakdown(char, defs);
+    if char.current_hp > max_hp.total {
+        issues.push(Issue::new(
+            Severity::Warning,
+            IssueKind::HpAboveMax,
+            format!("Current HP {} exceeds max HP {}.", char.current_hp, max_hp.total),
+        ));
+    }
+
+    issues.sort_by_key(|issue| std::cmp::Reverse(issue.severity));
+    issues
+}
+
+pub fn has_errors(issues: &[Issue]) -> bool {
+    issues.iter().any(|i| i.severity == Severity::Error)
+}
+
+/// Plain-text report, one issue per line, as printed by `--validate`.
+pub fn report(name: &str, issues: &[Issue]) -> String {
+    if issues.is_empty() {
+        return format!("{}: no issues found", name);
+    }
+    let mut lines = vec![format!("{}: {} issue(s)", name, issues.len())];
+    lines.extend(issues.iter().map(|issue| format!("  {}", issue)));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Ability, Item, Modifier, StatTarget, Wound};
+    use crate::origins::builtin_origins;
+
+    fn kinds(char: &Character) -> Vec<IssueKind> {
+        validate(char, &builtin_origins()).into_iter().map(|i| i.kind).collect()
+    }
+
+    #[test]
+    fn test_default_character_is_valid() {
+        assert!(validate(&Character::default(), &builtin_origins()).is_empty());
+    }
+
+    #[test]
+    fn test_rule_violations() {
+        let mut char = Character {
+            abilities: (0..4)
+                .map(|i| Ability {
+                    name: format!("Ability {}", i),
+                    prepared: true,
+                    ..Ability::default()
+                })
+                .collect(),
+            ..Character::default()
+        };
+        char.expended_spell_slots = char.attributes.intelligence + 1;
+
+        let issues = validate(&char, &builtin_origins());
+        assert!(has_errors(&issues));
+        assert_eq!(
+            kinds(&char),
+            vec![IssueKind::TooManyPrepared, IssueKind::SpellSlotsOverspent]
+        );
+
+        char.abilities.truncate(3);
+        char.expended_spell_slots = 0;
+        let mut tent = Item::new("Tent");
+        tent.slots = 10;
+        char.inventory.push(tent);
+        let issues = validate(&char, &builtin_origins());
+        assert!(!has_errors(&issues));
+        assert_eq!(kinds(&char), vec![IssueKind::OverCarryingCapacity]);
+    }
+
+    #[test]
+    fn test_wounds_below_floor() {
+        let mut char = Character {
+            wounds: vec![Wound::default(); 4],
+            current_hp: 1,
+            ..Character::default()
+        };
+        char.attributes.endurance = 2;
+        assert_eq!(kinds(&char), vec![IssueKind::WoundsExceedHp]);
+        assert!(report("Aster", &validate(&char, &builtin_origins())).contains("below 1"));
+    }
+
+    #[test]
+    fn test_max_hp_modifier_is_not_a_wound_issue() {
+        let char = Character {
+            modifiers: vec![Modifier {
+                target: StatTarget::MaxHp,
+                value: -20,
+                source: "Curse".to_string(),
+                active: true,
+            }],
+            current_hp: 1,
+            ..Character::default()
+        };
+        assert!(logic::max_hp_breakdown(&char, &builtin_origins()).clamped);
+        assert!(kinds(&char).is_empty());
+    }
+
+    #[test]
+    fn test_attribute_range_matches_sheet() {
+        let mut char = Character::default();
+        char.attributes.luck = 0;
+        assert_eq!(
+            kinds(&char),
+            vec![IssueKind::AttributeOutOfRange(AttributeField::Luck)]
+        );
+        assert!(report("Aster", &validate(&char, &builtin_origins())).contains("between 1 and 4"));
+    }
+}
@@ -1,11 +1,17 @@
-use crate::app::CharacterSheet;
+use crate::app::{CharacterSheet, PendingAction};
+use crate::backup::{self, Backup};
+use crate::creation::{CreationDraft, CreationStep};
+use crate::encounter;
 use crate::logic;
-use crate::message::{AttributeField, Message, OffsetField};
-use crate::model::Origin;
+use crate::message::{AttributeField, ItemField, Message, ModifierOwner, PurchaseKind};
+use crate::model::{ConditionDuration, DurationKind, Modifier, Origin, StatTarget, WoundHealing};
+use crate::origins::{self, OriginDef};
+use crate::recovery::RecoverySnapshot;
+use crate::validation::{self, Severity};
 use iced::font;
 use iced::widget::{
     button, checkbox, column, container, opaque, pick_list, row, scrollable, stack, text,
-    text_editor, text_input, Space,
+    text_editor, text_input, tooltip, Space,
 };
 use iced::{alignment, Alignment, Color, Element, Font, Length};
 
@@ -67,12 +73,36 @@ pub fn view(state: &CharacterSheet) -> Element<'_, Message> {
         column![
             view_header(state),
             row![
-                container(view_attributes(state)).width(Length::FillPortion(1)),
-                container(view_vitals(state)).width(Length::FillPortion(1)),
-                container(view_traits(state)).width(Length::FillPortion(1)),
+                container(column![view_attributes(state), view_experience(state)].spacing(20))
+                    .width(Length::FillPortion(1)),
+                container(
+                    column![
+                        view_vitals(state),
+                        view_encounter(state),
+                        view_conditions(state)
+                    ]
+                    .spacing(20)
+                )
+                    .width(Length::FillPortion(1)),
+                container(
+                    column![
+                        view_traits(state),
+                        view_dice_roller(state),
+                        view_roll_log(state),
+                        view_session_log(state)
+                    ]
+                    .spacing(20)
+                )
+                .width(Length::FillPortion(1)),
+            ]
+            .spacing(20),
+            row![
+                column![view_inventory(state), view_ledger(state)]
+                    .spacing(20)
+                    .width(Length::FillPortion(1)),
+                view_abilities(state),
             ]
             .spacing(20),
-            row![view_inventory(state), view_abilities(state),].spacing(20),
         ]
         .spacing(20)
         .padding(20),
@@ -84,18 +114,43 @@ pub fn view(state: &CharacterSheet) -> Element<'_, Message> {
         layers = layers.push(view_error_modal(error));
     } else if let Some(notification) = &state.notification {
         layers = layers.push(view_notification_modal(notification));
+    } else if let Some((path, dropped)) = &state.salvage_report {
+        layers = layers.push(view_salvage_report(path, dropped));
+    } else if let Some(snapshot) = &state.recovery {
+        layers = layers.push(view_recovery_prompt(snapshot));
+    } else if let Some(action) = state.unsaved_prompt {
+        layers = layers.push(view_unsaved_prompt(action));
+    } else if let Some(draft) = &state.creation {
+        layers = layers.push(view_creation(state, draft));
+    } else if let Some(increases) = &state.level_up {
+        layers = layers.push(view_level_up(state, increases));
     } else if state.is_editing {
         layers = layers.push(view_editor(state));
     } else if state.show_ability_browser {
         layers = layers.push(view_ability_browser(state));
+    } else if state.show_validation {
+        layers = layers.push(view_validation_report(state));
+    } else if let Some(versions) = &state.version_history {
+        layers = layers.push(view_version_history(state, versions));
     }
 
     if state.show_save_menu {
         let save_as_menu = container(
             container(
-                button(text("Save As").align_x(alignment::Horizontal::Center))
-                    .on_press(Message::SaveAsCharacter)
-                    .width(Length::Fill),
+                column![
+                    button(text("Save As").align_x(alignment::Horizontal::Center))
+                        .on_press(Message::SaveAsCharacter)
+                        .width(Length::Fill),
+                    button(text("History").align_x(alignment::Horizontal::Center))
+                        .on_press_maybe(
+                            state
+                                .current_file_path
+                                .as_ref()
+                                .map(|_| Message::ShowVersionHistory)
+                        )
+                        .width(Length::Fill),
+                ]
+                .spacing(5),
             )
             .style(container::bordered_box)
             .padding(5)
@@ -190,6 +245,234 @@ fn view_notification_modal(message: &str) -> Element<'_, Message> {
     .into()
 }
 
+fn modal<'a>(content: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+    opaque(
+        container(
+            container(content)
+                .style(container::bordered_box)
+                .padding(20),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(alignment::Horizontal::Center)
+        .align_y(alignment::Vertical::Center)
+        .style(|_| container::Style {
+            background: Some(
+                Color {
+                    r: 0.0,
+                    g: 0.0,
+                    b: 0.0,
+                    a: 0.8,
+                }
+                .into(),
+            ),
+            ..Default::default()
+        }),
+    )
+}
+
+fn view_salvage_report<'a>(path: &'a std::path::Path, dropped: &'a [String]) -> Element<'a, Message> {
+    let list = column(
+        dropped
+            .iter()
+            .map(|path| text(path).size(14).font(Font::MONOSPACE).into())
+            .collect::<Vec<_>>(),
+    )
+    .spacing(2);
+
+    modal(
+        column![
+            text("Character Partly Recovered").size(30),
+            text(format!(
+                "{:?} is damaged. These parts could not be read and were left out or reset to their defaults:",
+                path.file_name().unwrap_or_default()
+            )),
+            scrollable(list).height(200).width(Length::Fill),
+            text("Use Save As to keep the rescued character. The damaged file has not been changed.")
+                .size(14),
+            button("OK").on_press(Message::DismissSalvageReport),
+        ]
+        .spacing(20)
+        .padding(20)
+        .width(550),
+    )
+}
+
+fn view_recovery_prompt(snapshot: &RecoverySnapshot) -> Element<'_, Message> {
+    let source = match &snapshot.file_path {
+        Some(path) => format!("{:?}", path.file_name().unwrap_or_default()),
+        None => "a character that was never saved".to_string(),
+    };
+    modal(
+        column![
+            text("Recover Unsaved Changes").size(30),
+            text(format!(
+                "Changes to {} ({}) from {} were not saved before the sheet closed.",
+                snapshot.character.name,
+                source,
+                crate::timestamp::format(snapshot.saved_at)
+            )),
+            row![
+                button("Restore").on_press(Message::RestoreRecovery),
+                button("Discard")
+                    .style(button::danger)
+                    .on_press(Message::DiscardRecovery),
+            ]
+            .spacing(10),
+        ]
+        .spacing(20)
+        .padding(20)
+        .width(450),
+    )
+}
+
+fn view_unsaved_prompt<'a>(action: PendingAction) -> Element<'a, Message> {
+    modal(
+        column![
+            text("Unsaved Changes").size(30),
+            text(format!(
+                "Save changes to this character before {}?",
+                action
+            )),
+            row![
+                button("Save").on_press(Message::SaveBeforeContinuing),
+                button("Discard")
+                    .style(button::danger)
+                    .on_press(Message::DiscardChanges),
+                button("Cancel")
+                    .style(button::secondary)
+                    .on_press(Message::CancelPendingAction),
+            ]
+            .spacing(10),
+        ]
+        .spacing(20)
+        .padding(20)
+        .width(450),
+    )
+}
+
+fn view_version_history<'a>(
+    state: &'a CharacterSheet,
+    versions: &'a [Backup],
+) -> Element<'a, Message> {
+    let selected = state.selected_version.as_ref();
+
+    let list: Element<'a, Message> = if versions.is_empty() {
+        text("No earlier versions yet. One is kept each time the file is saved.").into()
+    } else {
+        column(
+            versions
+                .iter()
+                .enumerate()
+                .map(|(i, version)| {
+                    let label = button(text(crate::timestamp::format(version.saved_at)))
+                        .width(Length::Fill)
+                        .on_press(Message::VersionSelected(i));
+                    if selected.is_some_and(|(s, _)| *s == i) {
+                        label.into()
+                    } else {
+                        label.style(button::secondary).into()
+                    }
+                })
+                .collect::<Vec<_>>(),
+        )
+        .spacing(5)
+        .into()
+    };
+
+    let mut details = column![].spacing(5).width(Length::FillPortion(2));
+    if let Some((_, character)) = selected {
+        let changes = backup::diff(character, &state.character);
+        details = details.push(text("Changes made since this version:").size(18));
+        if changes.is_empty() {
+            details = details.push(text("None, it matches the current sheet."));
+        } else {
+            details = details.push(
+                scrollable(
+                    column(changes.into_iter().map(|line| text(line).size(14).into()))
+                        .spacing(2),
+                )
+                .height(300),
+            );
+        }
+        details = details.push(button("Restore This Version").on_press(Message::RestoreVersion));
+    } else if !versions.is_empty() {
+        details = details.push(text("Select a version to compare it with the current sheet."));
+    }
+
+    modal(
+        column![
+            text("Version History").size(30),
+            row![
+                scrollable(list).height(360).width(Length::FillPortion(1)),
+                details
+            ]
+            .spacing(20),
+            button("Close").on_press(Message::CloseVersionHistory),
+        ]
+        .spacing(20)
+        .padding(20)
+        .width(800),
+    )
+}
+
+fn view_validation_report(state: &CharacterSheet) -> Element<'_, Message> {
+    let issues = validation::validate(&state.character, &state.origins);
+
+    let list: Element<'_, Message> = if issues.is_empty() {
+        text("No rule violations found.").into()
+    } else {
+        column(
+            issues
+                .into_iter()
+                .map(|issue| {
+                    let label = text(issue.severity.to_string()).width(70);
+                    let label = match issue.severity {
+                        Severity::Error => label.style(text::danger),
+                        Severity::Warning => label,
+                    };
+                    row![label, text(issue.message)].spacing(10).into()
+                })
+                .collect::<Vec<_>>(),
+        )
+        .spacing(10)
+        .into()
+    };
+
+    modal(
+        column![
+            text("Sheet Check").size(30),
+            list,
+            button("Close").on_press(Message::ToggleValidationReport),
+        ]
+        .spacing(20)
+        .padding(20)
+        .width(500),
+    )
+}
+
+/// Wraps a derived value so hovering over it lists the parts it was summed from.
+fn with_breakdown<'a>(
+    content: impl Into<Element<'a, Message>>,
+    breakdown: logic::Breakdown,
+) -> Element<'a, Message> {
+    let lines = column(
+        breakdown
+            .lines()
+            .into_iter()
+            .map(|line| text(line).size(14).into())
+            .collect::<Vec<_>>(),
+    )
+    .spacing(2);
+
+    tooltip(
+        content,
+        container(lines).style(container::bordered_box).padding(8),
+        tooltip::Position::Bottom,
+    )
+    .into()
+}
+
 fn view_header(state: &CharacterSheet) -> Element<'_, Message> {
     let save_group = row![
         button("Save").on_press(Message::SaveCharacter),
@@ -198,18 +481,49 @@ fn view_header(state: &CharacterSheet) -> Element<'_, Message> {
     .spacing(2)
     .align_y(Alignment::Center);
 
-    let left_group = row![
+    let mut left_group = row![
         text(&state.character.name).size(30).width(Length::Fill),
         text(format!("Lvl {}", state.character.level)).size(24),
-        text(format!("{}", state.character.origin.to_string())).size(24),
-        button("Edit Character").on_press(Message::ToggleEditor),
+        text(origins::name(&state.origins, &state.character.origin)).size(24),
     ]
     .spacing(20)
     .align_y(Alignment::Center);
 
+    let issues = validation::validate(&state.character, &state.origins);
+    if !issues.is_empty() {
+        let badge = button(text(format!("⚠ {}", issues.len()))).on_press(Message::ToggleValidationReport);
+        left_group = left_group.push(if validation::has_errors(&issues) {
+            badge.style(button::danger)
+        } else {
+            badge.style(button::secondary)
+        });
+    }
+
+    let left_group = left_group.push(button("Edit Character").on_press(Message::ToggleEditor));
+
+    let history_group = row![
+        tooltip(
+            button("↶")
+                .style(button::secondary)
+                .on_press_maybe(state.history.can_undo().then_some(Message::Undo)),
+            "Undo (Ctrl+Z)",
+            tooltip::Position::Bottom,
+        ),
+        tooltip(
+            button("↷")
+                .style(button::secondary)
+                .on_press_maybe(state.history.can_redo().then_some(Message::Redo)),
+            "Redo (Ctrl+Shift+Z)",
+            tooltip::Position::Bottom,
+        ),
+    ]
+    .spacing(2);
+
     row![
         left_group.width(Length::Fill),
+        history_group,
         save_group,
+        button("New").on_press(Message::StartCreation),
         button("Load").on_press(Message::LoadCharacter),
     ]
     .spacing(20)
@@ -219,9 +533,13 @@ fn view_header(state: &CharacterSheet) -> Element<'_, Message> {
 }
 
 fn view_attributes(state: &CharacterSheet) -> Element<'_, Message> {
-    let attr_row = |label: &'static str, val: i32| {
+    let attr_row = |field: AttributeField| {
+        let val = logic::attribute_value(&state.character, field);
         row![
-            text(label).width(100),
+            button(text(field.to_string()))
+                .style(button::text)
+                .on_press(Message::RollAttributeCheck(field))
+                .width(120),
             text(val.to_string())
                 .size(20)
                 .width(30)
@@ -229,48 +547,416 @@ fn view_attributes(state: &CharacterSheet) -> Element<'_, Message> {
         ]
         .spacing(10)
         .align_y(Alignment::Center)
+        .into()
     };
 
     column![
         text("Attributes").size(24),
-        attr_row("Strength", state.character.attributes.strength),
-        attr_row("Dexterity", state.character.attributes.dexterity),
-        attr_row("Endurance", state.character.attributes.endurance),
-        attr_row("Faith", state.character.attributes.faith),
-        attr_row("Will", state.character.attributes.will),
-        attr_row("Intelligence", state.character.attributes.intelligence),
-        attr_row("Luck", state.character.attributes.luck),
+        text("Click an attribute to roll a check").size(14),
+        column(AttributeField::all().map(attr_row)).spacing(5),
     ]
     .spacing(10)
     .padding(10)
     .into()
 }
 
-fn view_vitals(state: &CharacterSheet) -> Element<'_, Message> {
-    let max_hp = logic::calculate_max_hp(&state.character);
-    let speed = logic::calculate_movement_speed(&state.character);
-    let ac = logic::calculate_armor_class(&state.character);
-    let crit_start = logic::calculate_crit_range(&state.character);
-    let spell_slots_max = logic::calculate_spell_slots(&state.character);
-    let miracle_slots_max = logic::calculate_miracle_slots(&state.character);
-
-    let wounds_row = row![
-        text("Wounds:"),
-        row((1..=4).map(|i| {
-            checkbox(state.character.wounds >= i)
-                .on_toggle(move |checked| {
-                    if checked {
-                        Message::WoundsChanged(i)
+fn view_experience(state: &CharacterSheet) -> Element<'_, Message> {
+    let progress = match logic::xp_for_next_level(&state.character) {
+        Some(needed) => format!("{} / {} XP", state.character.xp, needed),
+        None => format!("{} XP (max level)", state.character.xp),
+    };
+
+    let mut content = column![
+        text("Experience").size(24),
+        text(progress).size(20),
+        row![
+            text_input("+XP", &state.xp_input)
+                .on_input(Message::XpInputChanged)
+                .on_submit(Message::AddXp)
+                .width(80),
+            button("Add XP").on_press(Message::AddXp),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+    ]
+    .spacing(10)
+    .padding(10);
+
+    if logic::can_level_up(&state.character) {
+        content = content.push(
+            button(text(format!("Level up to {}!", state.character.level + 1)))
+                .style(button::success)
+                .on_press(Message::StartLevelUp),
+        );
+    }
+
+    for record in state.character.level_history.iter().rev() {
+        let mut line = format!(
+            "{}  Lvl {} → {}: Max HP {} → {}, Prepared {} → {}",
+            crate::timestamp::format(record.timestamp),
+            record.from_level,
+            record.to_level,
+            record.max_hp_before,
+            record.max_hp_after,
+            record.prepared_slots_before,
+            record.prepared_slots_after
+        );
+        if !record.attribute_increases.is_empty() {
+            line.push_str(&format!(", {}", record.attribute_increases.join(", ")));
+        }
+        content = content.push(text(line).size(12));
+    }
+
+    content.into()
+}
+
+fn view_level_up<'a>(state: &'a CharacterSheet, increases: &[AttributeField]) -> Element<'a, Message> {
+    let mut preview = state.character.clone();
+    logic::apply_level_up(&mut preview, &state.origins, increases);
+
+    let compare_row = |label: &'static str, before: i32, after: i32| {
+        row![
+            text(label).width(150),
+            text(format!("{} → {}", before, after)).size(20),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center)
+    };
+
+    let remaining = logic::LEVEL_UP_ATTRIBUTE_POINTS - increases.len() as i32;
+    let attr_max = logic::max_attribute_value(preview.level);
+
+    let attr_choices = column(AttributeField::all().map(|field| {
+        let current = logic::attribute_value(&state.character, field);
+        let selected = increases.contains(&field);
+        let toggle = checkbox(selected).label(format!("{} ({})", field, current));
+        let toggle = if selected || (remaining > 0 && current < attr_max) {
+            toggle.on_toggle(move |b| Message::LevelUpAttributeChanged(field, b))
+        } else {
+            toggle
+        };
+        toggle.into()
+    }))
+    .spacing(5);
+
+    let content = column![
+        text(format!(
+            "Level Up: {} → {}",
+            state.character.level, preview.level
+        ))
+        .size(30),
+        compare_row(
+            "Max HP",
+            logic::calculate_max_hp(&state.character, &state.origins),
+            logic::calculate_max_hp(&preview, &state.origins)
+        ),
+        compare_row(
+            "Prepared Abilities",
+            logic::calculate_prepared_slots(&state.character),
+            logic::calculate_prepared_slots(&preview)
+        ),
+        text(format!("Attribute increases ({} remaining)", remaining)).size(20),
+        attr_choices,
+        row![
+            button("Confirm").on_press(Message::ConfirmLevelUp),
+            button("Cancel")
+                .style(button::secondary)
+                .on_press(Message::CancelLevelUp),
+        ]
+        .spacing(10),
+    ]
+    .spacing(15)
+    .padding(20);
+
+    modal(content)
+}
+
+fn view_creation<'a>(state: &'a CharacterSheet, draft: &'a CreationDraft) -> Element<'a, Message> {
+    let step_body: Element<'a, Message> = match draft.step {
+        CreationStep::Identity => column![
+            row![
+                text("Name:").width(80),
+                text_input("Character name", &draft.name)
+                    .on_input(Message::CreationNameChanged)
+                    .on_submit(Message::CreationNext)
+                    .width(300)
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+            row![
+                text("Origin:").width(80),
+                pick_list(
+                    state.origins.as_slice(),
+                    origins::find(&state.origins, &draft.origin),
+                    |def: OriginDef| Message::CreationOriginSelected(Origin::new(def.id))
+                )
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+            column(
+                origins::traits(&state.origins, &draft.origin)
+                    .iter()
+                    .map(|t| text(format!("• {}", t)).size(14).into())
+                    .collect::<Vec<_>>()
+            )
+            .spacing(5)
+            .width(500),
+        ]
+        .spacing(10)
+        .into(),
+        CreationStep::Attributes => {
+            let attr_row = |field: AttributeField| {
+                let val = draft.attribute(field);
+                row![
+                    text(field.to_string()).width(100),
+                    button("-").on_press(Message::CreationAttributeChanged(field, val - 1)),
+                    text(val.to_string())
+                        .size(20)
+                        .width(30)
+                        .align_x(alignment::Horizontal::Center),
+                    button("+").on_press(Message::CreationAttributeChanged(field, val + 1)),
+                    text(if draft.base_attribute(field) > 1 {
+                        format!("(starts at {})", draft.base_attribute(field))
                     } else {
-                        Message::WoundsChanged(i - 1)
-                    }
-                })
+                        String::new()
+                    })
+                    .size(14),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center)
                 .into()
-        }))
+            };
+
+            column![
+                row![
+                    text("Point budget:"),
+                    text_input("8", &state.creation_budget_input)
+                        .on_input(Message::CreationBudgetChanged)
+                        .width(60),
+                    text(format!("{} remaining", draft.points_remaining())),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+                column(AttributeField::all().map(attr_row)).spacing(5),
+            ]
+            .spacing(10)
+            .into()
+        }
+        CreationStep::Equipment => {
+            let items = column(draft.items.iter().enumerate().map(|(i, item)| {
+                row![
+                    text(item).width(Length::Fill),
+                    button("🗑").on_press(Message::CreationRemoveItem(i)),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .into()
+            }))
+            .spacing(5);
+
+            column![
+                row![
+                    text("Starting tender:"),
+                    text_input("200", &state.creation_tender_input)
+                        .on_input(Message::CreationTenderChanged)
+                        .width(80),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+                row![
+                    text_input("Starting item", &state.creation_item_input)
+                        .on_input(Message::CreationItemInputChanged)
+                        .on_submit(Message::CreationAddItem)
+                        .width(Length::Fill),
+                    button("Add").on_press(Message::CreationAddItem),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+                items,
+            ]
+            .spacing(10)
+            .width(500)
+            .into()
+        }
+        CreationStep::Abilities => {
+            let slots = logic::calculate_prepared_slots(&draft.build(&state.origins));
+            let list = column(state.available_abilities.iter().map(|ability| {
+                let selected = draft.has_ability(&ability.name);
+                let pick = ability.clone();
+                row![
+                    checkbox(selected)
+                        .label(&ability.name)
+                        .on_toggle(move |b| Message::CreationAbilityToggled(pick.clone(), b))
+                        .width(Length::Fill),
+                    text(&ability.tags).size(14),
+                ]
+                .spacing(10)
+                .into()
+            }))
+            .spacing(5);
+
+            let list: Element<'a, Message> = if state.available_abilities.is_empty() {
+                text("No abilities found in the abilities folder.").into()
+            } else {
+                scrollable(list).height(300).into()
+            };
+
+            column![
+                text(format!("Picked {}/{}", draft.abilities.len(), slots)),
+                list
+            ]
+            .spacing(10)
+            .width(500)
+            .into()
+        }
+        CreationStep::Review => {
+            let preview = draft.build(&state.origins);
+            let attrs = AttributeField::all()
+                .map(|f| format!("{} {}", f, preview.attributes.get(f)))
+                .join(", ");
+            column![
+                text(format!(
+                    "{} the {}",
+                    preview.name,
+                    origins::name(&state.origins, &preview.origin)
+                ))
+                .size(20),
+                text(attrs),
+                text(format!(
+                    "Max HP {}, Speed {}, AC {}",
+                    logic::calculate_max_hp(&preview, &state.origins),
+                    logic::calculate_movement_speed(&preview, &state.origins),
+                    logic::calculate_armor_class(&preview)
+                )),
+                text(format!("Tender: {}", logic::calculate_tender(&preview))),
+                text(format!("Items: {}", draft.items.join(", "))),
+                text(format!(
+                    "Abilities: {}",
+                    draft
+                        .abilities
+                        .iter()
+                        .map(|a| a.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )),
+            ]
+            .spacing(10)
+            .width(500)
+            .into()
+        }
+    };
+
+    let errors = column(
+        state
+            .creation_errors
+            .iter()
+            .map(|e| text(e).style(text::danger).into())
+            .collect::<Vec<_>>(),
+    )
+    .spacing(5);
+
+    let back = button("Back").style(button::secondary);
+    let back = if draft.step.previous().is_some() {
+        back.on_press(Message::CreationBack)
+    } else {
+        back
+    };
+
+    let forward = if draft.step == CreationStep::Review {
+        button("Create Character").on_press(Message::CreationFinish)
+    } else {
+        button("Next").on_press(Message::CreationNext)
+    };
+
+    let content = column![
+        text("New Character").size(30),
+        text(format!(
+            "Step {} of {}: {}",
+            draft.step.index() + 1,
+            CreationStep::all().len(),
+            draft.step
+        ))
+        .size(20),
+        step_body,
+        errors,
+        row![
+            button("Cancel")
+                .style(button::secondary)
+                .on_press(Message::CancelCreation),
+            Space::new().width(Length::Fill),
+            back,
+            forward,
+        ]
         .spacing(10)
+        .width(500),
     ]
-    .spacing(10)
-    .align_y(Alignment::Center);
+    .spacing(20)
+    .padding(20);
+
+    modal(content)
+}
+
+fn view_vitals(state: &CharacterSheet) -> Element<'_, Message> {
+    let max_hp = logic::max_hp_breakdown(&state.character, &state.origins);
+    let speed = logic::movement_speed_breakdown(&state.character, &state.origins);
+    let ac = logic::armor_class_breakdown(&state.character);
+    let dr = logic::calculate_damage_reduction(&state.character);
+    let crit = logic::crit_range_breakdown(&state.character);
+    let spell_slots_max = logic::spell_slots_breakdown(&state.character);
+    let miracle_slots_max = logic::miracle_slots_breakdown(&state.character);
+
+    let mut wounds_col = column![text(format!("Wounds: {}", state.character.wounds.len()))].spacing(5);
+    for (i, wound) in state.character.wounds.iter().enumerate() {
+        let mut status = wound.healing.to_string();
+        if wound.healing == WoundHealing::Treatment && wound.treated {
+            status = "Treated".to_string();
+        }
+        if wound.taken_at != 0 {
+            status = format!("{}, {}", crate::timestamp::format(wound.taken_at), status);
+        }
+
+        let mut wound_row = row![
+            column![text(&wound.description), text(status).size(12)].width(Length::Fill),
+        ]
+        .spacing(5)
+        .align_y(Alignment::Center);
+        if wound.healing == WoundHealing::Treatment && !wound.treated {
+            wound_row = wound_row.push(
+                button("Treat")
+                    .style(button::secondary)
+                    .on_press(Message::TreatWound(i)),
+            );
+        }
+        wounds_col = wounds_col.push(
+            wound_row.push(
+                button("Heal")
+                    .style(button::secondary)
+                    .on_press(Message::HealWound(i)),
+            ),
+        );
+    }
+    wounds_col = wounds_col.push(
+        row![
+            text_input("Describe a new wound", &state.wound_input)
+                .on_input(Message::WoundInputChanged)
+                .on_submit(Message::AddWound)
+                .width(Length::Fill),
+            pick_list(
+                WoundHealing::all().to_vec(),
+                Some(state.wound_healing),
+                Message::WoundHealingSelected
+            ),
+            button("Add").on_press(Message::AddWound),
+        ]
+        .spacing(5)
+        .align_y(Alignment::Center),
+    );
+    if logic::wounds_at_hp_floor(&state.character, &state.origins) {
+        wounds_col = wounds_col.push(
+            text("⚠ Wounds have reduced max HP to 1: the character is dying")
+                .style(text::danger),
+        );
+    }
 
     let hp_row = row![
         text("HP:").width(20),
@@ -278,7 +964,7 @@ fn view_vitals(state: &CharacterSheet) -> Element<'_, Message> {
             .on_input(Message::HpInputChanged)
             .width(50)
             .align_x(alignment::Horizontal::Center),
-        text(format!("/ {}", max_hp)).size(20).width(50),
+        with_breakdown(text(format!("/ {}", max_hp.total)).size(20).width(50), max_hp),
         Space::new().width(10),
         button("-").on_press(Message::ApplyHpModifier(-1)),
         text_input("Mod", &state.hp_modifier)
@@ -292,101 +978,480 @@ fn view_vitals(state: &CharacterSheet) -> Element<'_, Message> {
 
     let resource_ticker = |label: &'static str,
                            input_val: &String,
-                           max: i32,
+                           max: logic::Breakdown,
                            on_change: fn(String) -> Message,
                            on_adjust: fn(i32) -> Message| {
         row![
-            text(label).width(80),
-            button("-").on_press(on_adjust(-1)),
-            text_input("0", input_val)
-                .on_input(on_change)
-                .width(40)
-                .align_x(alignment::Horizontal::Center),
-            button("+").on_press(on_adjust(1)),
-            text(format!("/ {}", max)).size(20),
+            text(label).width(80),
+            button("-").on_press(on_adjust(-1)),
+            text_input("0", input_val)
+                .on_input(on_change)
+                .width(40)
+                .align_x(alignment::Horizontal::Center),
+            button("+").on_press(on_adjust(1)),
+            with_breakdown(text(format!("/ {}", max.total)).size(20), max),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center)
+    };
+
+    column![
+        text("Vitals").size(24),
+        wounds_col,
+        hp_row,
+        row![
+            text("Speed:"),
+            with_breakdown(text(speed.total.to_string()).size(20), speed),
+            text(if logic::is_slowed_by_armour(&state.character, &state.origins) {
+                "(slowed by armour)"
+            } else {
+                ""
+            })
+            .size(14)
+            .style(text::danger),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+        row![
+            text("AC:"),
+            with_breakdown(text(ac.total.to_string()).size(20), ac),
+            text("(+"),
+            text_input("0", &state.armor_bonus_input)
+                .on_input(Message::ArmorBonusChanged)
+                .width(40),
+            text("Bonus)"),
+            Space::new().width(10),
+            text("DR:"),
+            with_breakdown(
+                text(dr.to_string()).size(20),
+                logic::damage_reduction_breakdown(&state.character)
+            ),
+            text_input("e.g. /holy", &state.dr_qualifier_input)
+                .on_input(Message::DrQualifierChanged)
+                .width(100)
+        ]
+        .spacing(5)
+        .align_y(Alignment::Center),
+        row![
+            text("Crit Range:"),
+            with_breakdown(
+                text(if crit.total == 12 {
+                    "12".to_string()
+                } else {
+                    format!("{}-12", crit.total)
+                })
+                .size(20),
+                crit
+            )
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+        row![
+            text("Tender:"),
+            text(logic::calculate_tender(&state.character).to_string()).size(20)
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+        Space::new().height(20),
+        text("Resources").size(24),
+        resource_ticker(
+            "Spells:",
+            &state.spells_input,
+            spell_slots_max,
+            Message::SpellsInputChanged,
+            Message::AdjustSpells
+        ),
+        resource_ticker(
+            "Miracles:",
+            &state.miracles_input,
+            miracle_slots_max,
+            Message::MiraclesInputChanged,
+            Message::AdjustMiracles
+        ),
+        row(state.rest_rules.iter().enumerate().map(|(i, rule)| {
+            button(text(&rule.name))
+                .style(button::secondary)
+                .on_press(Message::TakeRest(i))
+                .into()
+        }))
+        .spacing(10),
+    ]
+    .spacing(10)
+    .padding(10)
+    .into()
+}
+
+fn view_encounter(state: &CharacterSheet) -> Element<'_, Message> {
+    let Some(encounter) = &state.character.encounter else {
+        return row![
+            text("Encounter").size(24).width(Length::Fill),
+            button("Start Encounter").on_press(Message::StartEncounter),
+        ]
+        .padding(10)
+        .align_y(Alignment::Center)
+        .into();
+    };
+
+    let pips = |remaining: i32, total: i32| {
+        (0..total)
+            .map(|i| if i < remaining { "●" } else { "○" })
+            .collect::<String>()
+    };
+
+    let punishes = column(
+        state
+            .character
+            .abilities
+            .iter()
+            .filter(|a| a.prepared && encounter::is_punish(a))
+            .map(|ability| {
+                let label = text(&ability.name);
+                if encounter.punish_available(ability) {
+                    label.style(text::success).into()
+                } else {
+                    label.style(text::danger).into()
+                }
+            })
+            .collect::<Vec<_>>(),
+    )
+    .spacing(2);
+
+    let damage_log = column(encounter.damage_log.iter().rev().map(|entry| {
+        let line = if entry.dealt {
+            format!("R{}  Dealt {}", entry.round, entry.amount)
+        } else if entry.raw != entry.amount {
+            format!("R{}  Took {} ({} - DR)", entry.round, entry.amount, entry.raw)
+        } else {
+            format!("R{}  Took {}", entry.round, entry.amount)
+        };
+        let line = if entry.note.is_empty() {
+            line
+        } else {
+            format!("{}  {}", line, entry.note)
+        };
+        let line = text(line).size(14);
+        if entry.dealt {
+            line.into()
+        } else {
+            line.style(text::danger).into()
+        }
+    }))
+    .spacing(2);
+
+    let mut content = column![
+        row![
+            text(format!("Round {}", encounter.round))
+                .size(24)
+                .width(Length::Fill),
+            button("Next Round").on_press(Message::NextRound),
+            button("End")
+                .style(button::secondary)
+                .on_press(Message::EndEncounter),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+        row![
+            text("Actions:"),
+            text(pips(encounter.actions_remaining, encounter::ACTIONS_PER_TURN)).size(20),
+            Space::new().width(20),
+            text("Reactions:"),
+            text(pips(encounter.reactions_remaining, encounter::REACTIONS_PER_ROUND)).size(20),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+        punishes,
+        row![
+            text_input("Damage", &state.damage_input)
+                .on_input(Message::DamageInputChanged)
+                .width(70),
+            text_input("Source / target", &state.damage_note_input)
+                .on_input(Message::DamageNoteChanged)
+                .width(Length::Fill),
+            button("Took")
+                .style(button::danger)
+                .on_press(Message::TakeDamage),
+            button("Dealt").on_press(Message::DealDamage),
+        ]
+        .spacing(5)
+        .align_y(Alignment::Center),
+        text(format!(
+            "Taken {} / Dealt {}",
+            encounter.damage_taken(),
+            encounter.damage_dealt()
+        ))
+        .size(14),
+        scrollable(damage_log).height(100),
+    ]
+    .spacing(10);
+
+    if let Some(error) = &state.encounter_error {
+        content = content.push(text(error).style(text::danger));
+    }
+
+    container(content)
+        .style(container::bordered_box)
+        .padding(10)
+        .into()
+}
+
+fn view_conditions(state: &CharacterSheet) -> Element<'_, Message> {
+    let mut list = column![].spacing(10);
+
+    for (i, condition) in state.character.conditions.iter().enumerate() {
+        if state.is_editing_conditions {
+            let mut duration_row = row![
+                text("Duration"),
+                pick_list(
+                    DurationKind::all().to_vec(),
+                    Some(condition.duration.kind()),
+                    move |kind| Message::ConditionDurationSelected(i, kind)
+                ),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center);
+
+            if let ConditionDuration::Rounds(n) = condition.duration {
+                duration_row = duration_row
+                    .push(button("-").on_press(Message::ConditionRoundsChanged(i, n.saturating_sub(1))))
+                    .push(
+                        text(n.to_string())
+                            .width(30)
+                            .align_x(alignment::Horizontal::Center),
+                    )
+                    .push(button("+").on_press(Message::ConditionRoundsChanged(i, n + 1)));
+            }
+
+            let editor = column![
+                row![
+                    text_input("Condition Name", &condition.name)
+                        .on_input(move |s| Message::ConditionNameChanged(i, s))
+                        .width(Length::Fill),
+                    button("🗑").on_press(Message::ExpireCondition(i)),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+                duration_row,
+                text_input("Notes", &condition.notes)
+                    .on_input(move |s| Message::ConditionNotesChanged(i, s)),
+                view_modifiers(ModifierOwner::Condition(i), &condition.modifiers),
+            ]
+            .spacing(5);
+
+            list = list.push(
+                container(editor)
+                    .style(container::bordered_box)
+                    .padding(10),
+            );
+        } else {
+            let mut details = column![
+                row![
+                    text(&condition.name).size(18).width(Length::Fill),
+                    text(condition.duration.to_string()).size(14),
+                    button("Expire")
+                        .style(button::secondary)
+                        .on_press(Message::ExpireCondition(i)),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            ]
+            .spacing(2);
+
+            if !condition.modifiers.is_empty() {
+                details = details.push(text(modifier_summary(&condition.modifiers)).size(14));
+            }
+            if !condition.notes.is_empty() {
+                details = details.push(text(&condition.notes).size(14));
+            }
+
+            list = list.push(
+                container(details)
+                    .style(container::bordered_box)
+                    .padding(5)
+                    .width(Length::Fill),
+            );
+        }
+    }
+
+    if state.character.conditions.is_empty() {
+        list = list.push(text("No active effects"));
+    }
+
+    let mut header_controls = row![
+        text("Conditions").size(24),
+        Space::new().width(Length::Fill),
+        button("Next Round")
+            .style(button::secondary)
+            .on_press_maybe(
+                (!state.character.conditions.is_empty()).then_some(Message::AdvanceConditions)
+            ),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center);
+
+    if state.is_editing_conditions {
+        header_controls = header_controls.push(button("Add").on_press(Message::AddCondition));
+    }
+
+    header_controls = header_controls.push(
+        button(if state.is_editing_conditions {
+            "Done Editing"
+        } else {
+            "Edit"
+        })
+        .on_press(Message::ToggleEditConditions),
+    );
+
+    column![header_controls, list]
+        .padding(10)
+        .spacing(20)
+        .into()
+}
+
+fn view_traits(state: &CharacterSheet) -> Element<'_, Message> {
+    let origin_traits = origins::traits(&state.origins, &state.character.origin);
+    let traits_col = column(
+        origin_traits
+            .iter()
+            .map(|t| text(format!("• {}", t)).into())
+            .collect::<Vec<_>>(),
+    )
+    .spacing(5);
+
+    column![
+        text(format!(
+            "{} Traits",
+            origins::name(&state.origins, &state.character.origin)
+        ))
+        .size(24),
+        traits_col
+    ]
+    .padding(10)
+    .into()
+}
+
+fn view_dice_roller(state: &CharacterSheet) -> Element<'_, Message> {
+    let quick_rolls = row(["1d6", "1d12", "2d6", "2d12kh1", "2d12kl1"].map(|expr| {
+        button(text(expr))
+            .style(button::secondary)
+            .on_press(Message::RollExpression(expr.to_string()))
+            .into()
+    }))
+    .spacing(5);
+
+    let result: Element<'_, Message> = if let Some(error) = &state.dice_error {
+        text(error).style(text::danger).into()
+    } else if let Some(roll) = &state.last_roll {
+        let total = text(roll.total.to_string()).size(30);
+        let total = if roll.crit {
+            total.style(text::success)
+        } else {
+            total
+        };
+        row![total, text(roll.summary()).width(Length::Fill)]
+        .spacing(10)
+        .align_y(Alignment::Center)
+        .into()
+    } else {
+        text("No rolls yet").into()
+    };
+
+    column![
+        text("Dice Roller").size(24),
+        row![
+            text_input("e.g. 2d6+3", &state.dice_input)
+                .on_input(Message::DiceExpressionChanged)
+                .on_submit(Message::RollDice)
+                .width(Length::Fill),
+            button("Roll").on_press(Message::RollDice),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+        quick_rolls,
+        result,
+    ]
+    .spacing(10)
+    .padding(10)
+    .into()
+}
+
+fn view_roll_log(state: &CharacterSheet) -> Element<'_, Message> {
+    let log = &state.character.roll_log;
+
+    let entries = column(log.iter().rev().map(|entry| {
+        let total = text(entry.total.to_string()).size(20).width(40);
+        let total = if entry.crit {
+            total.style(text::success)
+        } else {
+            total
+        };
+        row![
+            total,
+            column![
+                text(format!("{} ({})", entry.label, entry.expression)),
+                text(format!(
+                    "{}  [{}]{}",
+                    crate::timestamp::format(entry.timestamp),
+                    entry.dice_summary(),
+                    if entry.crit { "  Critical!" } else { "" }
+                ))
+                .size(12),
+            ]
         ]
         .spacing(10)
         .align_y(Alignment::Center)
-    };
+        .into()
+    }))
+    .spacing(5);
 
-    column![
-        text("Vitals").size(24),
-        wounds_row,
-        hp_row,
-        row![text("Speed:"), text(speed.to_string()).size(20)]
-            .spacing(10)
-            .align_y(Alignment::Center),
+    let clear_controls: Element<'_, Message> = if state.confirming_clear_roll_log {
         row![
-            text("AC:"),
-            text(ac.to_string()).size(20),
-            text("(+"),
-            text_input("0", &state.armor_bonus_input)
-                .on_input(Message::ArmorBonusChanged)
-                .width(40),
-            text("Armor)"),
-            Space::new().width(10),
-            text("DR:"),
-            text_input("e.g. 5/holy", &state.dr_input)
-                .on_input(Message::DrChanged)
-                .width(100)
+            text("Sure?"),
+            button("Yes").on_press(Message::ConfirmClearRollLog),
+            button("No").on_press(Message::CancelClearRollLog),
         ]
         .spacing(5)
-        .align_y(Alignment::Center),
-        row![
-            text("Crit Range:"),
-            text(if crit_start == 12 {
-                "12".to_string()
-            } else {
-                format!("{}-12", crit_start)
-            })
-            .size(20)
-        ]
-        .spacing(10)
-        .align_y(Alignment::Center),
+        .align_y(Alignment::Center)
+        .into()
+    } else {
+        button("Clear")
+            .on_press_maybe((!log.is_empty()).then_some(Message::RequestClearRollLog))
+            .into()
+    };
+
+    column![
         row![
-            text("Tender:"),
-            text_input("200", &state.tender_input)
-                .on_input(Message::TenderChanged)
-                .width(80)
+            text(format!("Roll Log ({})", log.len()))
+                .size(24)
+                .width(Length::Fill),
+            button("Export").on_press_maybe((!log.is_empty()).then_some(Message::ExportRollLog)),
+            clear_controls,
         ]
         .spacing(10)
         .align_y(Alignment::Center),
-        Space::new().height(20),
-        text("Resources").size(24),
-        resource_ticker(
-            "Spells:",
-            &state.spells_input,
-            spell_slots_max,
-            Message::SpellsInputChanged,
-            Message::AdjustSpells
-        ),
-        resource_ticker(
-            "Miracles:",
-            &state.miracles_input,
-            miracle_slots_max,
-            Message::MiraclesInputChanged,
-            Message::AdjustMiracles
-        ),
+        scrollable(entries).height(200),
     ]
     .spacing(10)
     .padding(10)
     .into()
 }
 
-fn view_traits(state: &CharacterSheet) -> Element<'_, Message> {
-    let origin_traits = logic::get_origin_traits(state.character.origin);
-    let traits_col = column(
-        origin_traits
-            .iter()
-            .map(|t| text(format!("• {}", t)).into())
-            .collect::<Vec<_>>(),
-    )
+fn view_session_log(state: &CharacterSheet) -> Element<'_, Message> {
+    let log = &state.character.session_log;
+
+    let entries = column(log.iter().rev().map(|entry| {
+        column![
+            text(&entry.text),
+            text(crate::timestamp::format(entry.timestamp)).size(12),
+        ]
+        .into()
+    }))
     .spacing(5);
 
     column![
-        text(format!("{} Traits", state.character.origin.to_string())).size(24),
-        traits_col
+        text(format!("Session Log ({})", log.len())).size(24),
+        scrollable(entries).height(150),
     ]
+    .spacing(10)
     .padding(10)
     .into()
 }
@@ -406,18 +1471,6 @@ fn view_editor(state: &CharacterSheet) -> Element<'_, Message> {
         .align_y(Alignment::Center)
     };
 
-    let offset_row = |label: &'static str, val: &String, field: OffsetField| {
-        row![
-            text(label).width(150),
-            text_input("+0", val)
-                .on_input(move |s| Message::OffsetChanged(field, s))
-                .width(60)
-                .align_x(alignment::Horizontal::Center)
-        ]
-        .spacing(10)
-        .align_y(Alignment::Center)
-    };
-
     let content = column![
         text("Edit Character").size(30),
         row![
@@ -434,12 +1487,20 @@ fn view_editor(state: &CharacterSheet) -> Element<'_, Message> {
         ]
         .spacing(10)
         .align_y(Alignment::Center),
+        row![
+            text("XP Thresholds:"),
+            text_input("10, 25, 45, ...", &state.xp_thresholds_input)
+                .on_input(Message::XpThresholdsChanged)
+                .width(300)
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
         row![
             text("Origin:"),
             pick_list(
-                Origin::all(),
-                Some(state.character.origin),
-                Message::OriginSelected
+                state.origins.as_slice(),
+                origins::find(&state.origins, &state.character.origin),
+                |def: OriginDef| Message::OriginSelected(Origin::new(def.id))
             )
         ]
         .spacing(10)
@@ -485,44 +1546,11 @@ fn view_editor(state: &CharacterSheet) -> Element<'_, Message> {
             ]
             .spacing(10),
             column![
-                text("Derived Stat Overrides").size(20),
-                offset_row(
-                    "Speed Offset",
-                    &state.speed_offset_input,
-                    OffsetField::Speed
-                ),
-                offset_row(
-                    "Crit Range Offset",
-                    &state.crit_range_offset_input,
-                    OffsetField::CritRange
-                ),
-                offset_row(
-                    "Max Spells Offset",
-                    &state.max_spells_offset_input,
-                    OffsetField::MaxSpells
-                ),
-                offset_row(
-                    "Max Miracles Offset",
-                    &state.max_miracles_offset_input,
-                    OffsetField::MaxMiracles
-                ),
-                offset_row(
-                    "Max Abilities Offset",
-                    &state.max_abilities_offset_input,
-                    OffsetField::MaxAbilities
-                ),
-                offset_row(
-                    "Max Inventory Slots Offset",
-                    &state.max_inventory_slots_offset_input,
-                    OffsetField::MaxInventorySlots
-                ),
-                offset_row(
-                    "Max HP Offset",
-                    &state.max_hp_offset_input,
-                    OffsetField::MaxHp
-                ),
+                text("Modifiers").size(20),
+                view_modifiers(ModifierOwner::Character, &state.character.modifiers),
             ]
-            .spacing(10),
+            .spacing(10)
+            .width(420),
             column![
                 text("Theme Colors").size(20),
                 row![
@@ -584,54 +1612,370 @@ fn view_editor(state: &CharacterSheet) -> Element<'_, Message> {
     .into()
 }
 
+/// Editable list of modifiers with an on/off toggle, source, target and value
+/// for each, shared by the character editor, items and abilities.
+fn view_modifiers(owner: ModifierOwner, modifiers: &[Modifier]) -> Element<'_, Message> {
+    let mut col = column![].spacing(5);
+
+    for (idx, modifier) in modifiers.iter().enumerate() {
+        col = col.push(
+            row![
+                checkbox(modifier.active)
+                    .on_toggle(move |b| Message::ToggleModifier(owner, idx, b)),
+                text_input("Source", &modifier.source)
+                    .on_input(move |s| Message::ModifierSourceChanged(owner, idx, s))
+                    .width(Length::Fill),
+                pick_list(StatTarget::all().to_vec(), Some(modifier.target), move |t| {
+                    Message::ModifierTargetSelected(owner, idx, t)
+                }),
+                button("-").on_press(Message::ModifierValueChanged(
+                    owner,
+                    idx,
+                    modifier.value - 1
+                )),
+                text(format!("{:+}", modifier.value))
+                    .width(30)
+                    .align_x(alignment::Horizontal::Center),
+                button("+").on_press(Message::ModifierValueChanged(
+                    owner,
+                    idx,
+                    modifier.value + 1
+                )),
+                button("🗑").on_press(Message::RemoveModifier(owner, idx)),
+            ]
+            .spacing(5)
+            .align_y(Alignment::Center),
+        );
+    }
+
+    col.push(
+        button("Add Modifier")
+            .style(button::secondary)
+            .on_press(Message::AddModifier(owner)),
+    )
+    .into()
+}
+
 fn view_inventory(state: &CharacterSheet) -> Element<'_, Message> {
-    let total_slots = logic::calculate_carrying_slots(&state.character);
+    let total_slots = logic::calculate_carrying_slots(&state.character, &state.origins);
+    let used_slots = logic::calculate_used_slots(&state.character);
+
+    let mut items_col = column![].spacing(10);
+
+    for (i, item) in state.character.inventory.iter().enumerate() {
+        if state.is_editing_inventory {
+            let field_input = |placeholder: &'static str, value: String, field: ItemField| {
+                text_input(placeholder, &value)
+                    .on_input(move |s| Message::ItemChanged(i, field, s))
+            };
+
+            let mut editor_col = column![
+                row![
+                    field_input("Item Name", item.name.clone(), ItemField::Name)
+                        .width(Length::Fill),
+                    button("🗑").on_press(Message::RemoveItem(i)),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+                row![
+                    text("Slots"),
+                    field_input("1", item.slots.to_string(), ItemField::Slots).width(40),
+                    text("Qty"),
+                    field_input("1", item.quantity.to_string(), ItemField::Quantity).width(50),
+                    text("Price"),
+                    field_input("0", item.price.to_string(), ItemField::Price).width(60),
+                    field_input("Tags (e.g. Heavy, Armour)", item.tags.clone(), ItemField::Tags)
+                        .width(Length::Fill),
+                ]
+                .spacing(5)
+                .align_y(Alignment::Center),
+                field_input("Notes", item.notes.clone(), ItemField::Notes),
+                row![
+                    checkbox(item.weapon.is_some())
+                        .label("Weapon")
+                        .on_toggle(move |b| Message::ToggleItemWeapon(i, b)),
+                    checkbox(item.armour.is_some())
+                        .label("Armour")
+                        .on_toggle(move |b| Message::ToggleItemArmour(i, b)),
+                ]
+                .spacing(20),
+            ]
+            .spacing(5);
+
+            if let Some(weapon) = &item.weapon {
+                editor_col = editor_col.push(
+                    row![
+                        text("Damage"),
+                        field_input("1d6", weapon.damage.clone(), ItemField::Damage).width(80),
+                        text("Heavy Str"),
+                        field_input("0", weapon.heavy.to_string(), ItemField::HeavyStrength)
+                            .width(40),
+                        field_input(
+                            "Properties (e.g. Reach, Thrown)",
+                            weapon.properties.clone(),
+                            ItemField::Properties
+                        )
+                        .width(Length::Fill),
+                    ]
+                    .spacing(5)
+                    .align_y(Alignment::Center),
+                );
+            }
 
-    let display_count = total_slots.max(state.character.inventory.len() as i32) as usize;
+            if let Some(armour) = &item.armour {
+                editor_col = editor_col.push(
+                    row![
+                        text("AC"),
+                        field_input("0", armour.ac.to_string(), ItemField::ArmourAc).width(40),
+                        text("DR"),
+                        field_input("0", armour.dr.to_string(), ItemField::ArmourDr).width(40),
+                        text("Str Req"),
+                        field_input("0", armour.strength.to_string(), ItemField::ArmourStrength)
+                            .width(40),
+                        checkbox(armour.slow)
+                            .label("Slow")
+                            .on_toggle(move |b| Message::ToggleArmourSlow(i, b)),
+                    ]
+                    .spacing(5)
+                    .align_y(Alignment::Center),
+                );
+            }
 
-    let mut slots_col = column![].spacing(5);
+            editor_col = editor_col.push(view_modifiers(ModifierOwner::Item(i), &item.modifiers));
 
-    for i in 0..display_count {
-        let editor_content = state
-            .inventory_editors
-            .get(i)
-            .expect("Inventory editor should exist");
-        let label = if i < total_slots as usize {
-            format!("{}.", i + 1)
+            items_col = items_col.push(
+                container(editor_col)
+                    .style(container::bordered_box)
+                    .padding(10),
+            );
         } else {
-            format!("Over limit {}.", i + 1)
-        };
+            let name = if item.quantity != 1 {
+                format!("{} ×{}", item.name, item.quantity)
+            } else {
+                item.name.clone()
+            };
 
-        slots_col = slots_col.push(
-            row![
-                text(label).width(80),
-                text_editor(editor_content)
-                    .placeholder("Empty slot...")
-                    .height(Length::Shrink)
-                    .on_action(move |a| Message::InventoryAction(i, a))
+            let price = logic::calculate_purchase_price(&state.character, &state.origins, item);
+            let price = if price != item.price {
+                format!("{} T (+10%)", price)
+            } else {
+                format!("{} T", price)
+            };
+
+            let mut name_row = row![].spacing(10).align_y(Alignment::Center);
+            if item.weapon.is_some() || item.armour.is_some() || !item.modifiers.is_empty() {
+                name_row = name_row.push(
+                    checkbox(item.equipped).on_toggle(move |b| Message::ToggleItemEquipped(i, b)),
+                );
+            }
+
+            let mut details = column![
+                row![
+                    name_row.push(text(name).size(18)).width(Length::Fill),
+                    text(&item.tags).size(14),
+                    text(format!(
+                        "{} slot{}",
+                        item.slots,
+                        if item.slots == 1 { "" } else { "s" }
+                    ))
+                    .width(60),
+                    text(price).width(90),
+                    button("Sell")
+                        .style(button::secondary)
+                        .on_press(Message::SellItem(i)),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
             ]
-            .align_y(Alignment::Center),
-        );
+            .spacing(2);
+
+            if let Some(weapon) = &item.weapon {
+                let mut weapon_row = row![
+                    button(text(format!("🎲 {}", weapon.damage)))
+                        .style(button::secondary)
+                        .on_press(Message::RollWeaponDamage(i)),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center);
+
+                if weapon.heavy > 0 {
+                    weapon_row = weapon_row.push(text(format!("Heavy ({})", weapon.heavy)).size(14));
+                }
+                if !weapon.properties.is_empty() {
+                    weapon_row = weapon_row.push(text(&weapon.properties).size(14));
+                }
+                details = details.push(weapon_row);
+
+                if !logic::can_wield(&state.character, &state.origins, weapon) {
+                    details = details.push(
+                        text(format!(
+                            "⚠ Too heavy: needs Strength {} (yours counts as {})",
+                            weapon.heavy,
+                            logic::calculate_heavy_strength(&state.character, &state.origins)
+                        ))
+                        .size(14)
+                        .style(text::danger),
+                    );
+                }
+            }
+
+            if let Some(armour) = &item.armour {
+                let mut armour_text = format!("AC +{}, DR {}", armour.ac, armour.dr);
+                if armour.strength > 0 {
+                    armour_text.push_str(&format!(", Str {}", armour.strength));
+                }
+                if armour.slow {
+                    armour_text.push_str(", Slow");
+                }
+                details = details.push(text(armour_text).size(14));
+
+                if !logic::meets_armour_strength(&state.character, &state.origins, armour) {
+                    details = details.push(
+                        text(format!(
+                            "⚠ Needs Strength {}: slows you while worn",
+                            armour.strength
+                        ))
+                        .size(14)
+                        .style(text::danger),
+                    );
+                }
+            }
+
+            if !item.notes.is_empty() {
+                details = details.push(text(&item.notes).size(14));
+            }
+            if !item.modifiers.is_empty() {
+                details = details.push(text(modifier_summary(&item.modifiers)).size(14));
+            }
+
+            items_col = items_col.push(
+                container(details)
+                    .style(container::bordered_box)
+                    .padding(5)
+                    .width(Length::Fill),
+            );
+        }
     }
 
-    column![
+    if state.character.inventory.is_empty() {
+        items_col = items_col.push(text("No items"));
+    }
+
+    let summary = text(format!("({}/{} slots)", used_slots, total_slots)).size(20);
+    let summary = if used_slots > total_slots {
+        summary.style(text::danger)
+    } else {
+        summary
+    };
+
+    let mut header_controls = row![
+        text("Inventory").size(24),
+        with_breakdown(summary, logic::carrying_slots_breakdown(&state.character, &state.origins)),
+        Space::new().width(Length::Fill),
+    ]
+    .spacing(20)
+    .align_y(Alignment::Center);
+
+    if state.is_editing_inventory {
+        header_controls = header_controls.push(button("Add Item").on_press(Message::AddItem));
+    }
+
+    header_controls = header_controls.push(
+        button(if state.is_editing_inventory {
+            "Done Editing"
+        } else {
+            "Edit"
+        })
+        .on_press(Message::ToggleEditInventory),
+    );
+
+    column![header_controls, items_col]
+        .padding(10)
+        .spacing(20)
+        .into()
+}
+
+/// Comma-separated list of active modifiers, e.g. `+2 Max HP, -1 Speed`.
+fn modifier_summary(modifiers: &[Modifier]) -> String {
+    modifiers
+        .iter()
+        .filter(|m| m.active)
+        .map(|m| format!("{:+} {}", m.value, m.target))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn view_ledger(state: &CharacterSheet) -> Element<'_, Message> {
+    let entries = column(state.character.ledger.iter().rev().map(|entry| {
+        let amount = text(format!("{:+}", entry.amount)).size(18).width(60);
+        let amount = if entry.amount < 0 {
+            amount.style(text::danger)
+        } else {
+            amount.style(text::success)
+        };
+        row![
+            amount,
+            text(&entry.reason).width(Length::Fill),
+            text(crate::timestamp::format(entry.timestamp)).size(12),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center)
+        .into()
+    }))
+    .spacing(5);
+
+    let buy_row = row![
+        text("Buy"),
+        text_input("Item name", &state.buy_name_input)
+            .on_input(Message::BuyNameChanged)
+            .on_submit(Message::BuyItem)
+            .width(Length::Fill),
+        text_input("Price", &state.buy_price_input)
+            .on_input(Message::BuyPriceChanged)
+            .on_submit(Message::BuyItem)
+            .width(60),
+        pick_list(
+            PurchaseKind::all(),
+            Some(state.buy_kind),
+            Message::BuyKindSelected
+        ),
+        button("Buy").on_press(Message::BuyItem),
+    ]
+    .spacing(5)
+    .align_y(Alignment::Center);
+
+    let entry_row = row![
+        text("Entry"),
+        text_input("Reason", &state.ledger_reason_input)
+            .on_input(Message::LedgerReasonChanged)
+            .on_submit(Message::AddLedgerEntry)
+            .width(Length::Fill),
+        text_input("+/-", &state.ledger_amount_input)
+            .on_input(Message::LedgerAmountChanged)
+            .on_submit(Message::AddLedgerEntry)
+            .width(60),
+        button("Add").on_press(Message::AddLedgerEntry),
+    ]
+    .spacing(5)
+    .align_y(Alignment::Center);
+
+    let mut content = column![
         text(format!(
-            "Inventory ({}/{})",
-            state
-                .character
-                .inventory
-                .iter()
-                .filter(|s| !s.is_empty())
-                .count(),
-            total_slots
+            "Tender: {}",
+            logic::calculate_tender(&state.character)
         ))
         .size(24),
-        slots_col
+        buy_row,
+        entry_row,
     ]
-    .padding(10)
     .spacing(10)
-    .width(Length::FillPortion(1))
-    .into()
+    .padding(10);
+
+    if let Some(error) = &state.ledger_error {
+        content = content.push(text(error).style(text::danger));
+    }
+
+    content.push(scrollable(entries).height(200)).into()
 }
 
 fn view_ability_browser(state: &CharacterSheet) -> Element<'_, Message> {
@@ -852,7 +2196,8 @@ fn view_abilities(state: &CharacterSheet) -> Element<'_, Message> {
         .iter()
         .filter(|a| a.prepared)
         .count();
-    let max_prepared = logic::calculate_prepared_slots(&state.character);
+    let prepared_slots = logic::prepared_slots_breakdown(&state.character);
+    let max_prepared = prepared_slots.total;
 
     for (i, ability) in state.character.abilities.iter().enumerate() {
         if state.is_editing_abilities {
@@ -919,6 +2264,7 @@ fn view_abilities(state: &CharacterSheet) -> Element<'_, Message> {
                     .placeholder("Flavour Text (Description)")
                     .height(Length::Shrink)
                     .on_action(move |a| Message::AbilityDescChanged(i, a)),
+                view_modifiers(ModifierOwner::Ability(i), &ability.modifiers),
             ]
             .spacing(5);
 
@@ -928,20 +2274,49 @@ fn view_abilities(state: &CharacterSheet) -> Element<'_, Message> {
                     .padding(10),
             );
         } else {
-            let header_row = row![
-                checkbox(ability.prepared).on_toggle(move |b| Message::ToggleAbilityPrepared(i, b)),
+            let toggle = match &state.loadout_selection {
+                Some(selection) => checkbox(selection.contains(&i))
+                    .on_toggle(move |b| Message::LoadoutSelectionToggled(i, b)),
+                None => checkbox(ability.prepared)
+                    .on_toggle(move |b| Message::ToggleAbilityPrepared(i, b)),
+            };
+
+            let mut header_row = row![
+                toggle,
                 text(&ability.name).size(20).width(Length::Fill),
                 text(&ability.tags).size(16),
             ]
             .spacing(10)
             .align_y(Alignment::Center);
 
+            if ability.prepared && state.loadout_selection.is_none() {
+                let label = match logic::ability_slot(ability) {
+                    Some(kind) => format!(
+                        "Use ({} left)",
+                        logic::slots_remaining(&state.character, kind)
+                    ),
+                    None => "Use".to_string(),
+                };
+                header_row = header_row.push(button(text(label)).on_press(Message::UseAbility(i)));
+            }
+
             let body = spans_to_rich(&ability.body_spans, false);
             let desc = spans_to_rich(&ability.desc_spans, true);
             let desc = container(desc).style(container::bordered_box).padding(5);
 
+            let mut card = column![header_row].spacing(5);
+            if let Some((idx, error)) = &state.ability_use_error
+                && *idx == i
+            {
+                card = card.push(text(error).style(text::danger));
+            }
+            let mut card = card.push(body).push(desc);
+            if !ability.modifiers.is_empty() {
+                card = card.push(text(modifier_summary(&ability.modifiers)).size(14));
+            }
+
             list = list.push(
-                container(column![header_row, body, desc].spacing(5))
+                container(card)
                     .style(container::bordered_box)
                     .padding(10)
                     .width(Length::Fill),
@@ -949,9 +2324,16 @@ fn view_abilities(state: &CharacterSheet) -> Element<'_, Message> {
         }
     }
 
+    let counter = text(format!("(Prepared: {}/{})", prepared_count, max_prepared)).size(20);
+    let counter = if prepared_count > max_prepared as usize {
+        counter.style(text::danger)
+    } else {
+        counter
+    };
+
     let mut header_controls = row![
         text("Abilities").size(24),
-        text(format!("(Prepared: {}/{})", prepared_count, max_prepared)).size(20),
+        with_breakdown(counter, prepared_slots),
         Space::new().width(Length::Fill),
     ]
     .spacing(20)
@@ -972,9 +2354,91 @@ fn view_abilities(state: &CharacterSheet) -> Element<'_, Message> {
         .on_press(Message::ToggleEditAbilities),
     );
 
-    column![header_controls, list]
+    let mut content = column![header_controls].spacing(20);
+    if !state.is_editing_abilities {
+        content = content.push(view_loadouts(state, max_prepared));
+    }
+    if let Some(error) = &state.prepare_error {
+        content = content.push(text(error).style(text::danger));
+    }
+
+    content
+        .push(list)
         .padding(10)
-        .spacing(20)
         .width(Length::FillPortion(1))
         .into()
 }
+
+/// Saved loadouts and the controls for picking a set of abilities to
+/// prepare at once.
+fn view_loadouts(state: &CharacterSheet, max_prepared: i32) -> Element<'_, Message> {
+    let names: Vec<String> = state
+        .character
+        .loadouts
+        .iter()
+        .map(|l| l.name.clone())
+        .collect();
+    let selected = names
+        .iter()
+        .find(|n| **n == state.loadout_name_input.trim())
+        .cloned();
+
+    let mut saved = row![text("Loadouts:")]
+        .spacing(10)
+        .align_y(Alignment::Center);
+    if names.is_empty() {
+        saved = saved.push(text("None saved").size(14));
+    } else {
+        saved = saved.push(
+            pick_list(names, selected.clone(), Message::LoadoutSelected).placeholder("Choose..."),
+        );
+        if let Some(name) = selected {
+            saved = saved.push(
+                button("🗑")
+                    .style(button::secondary)
+                    .on_press(Message::DeleteLoadout(name)),
+            );
+        }
+    }
+
+    let save_row = row![
+        text_input("Loadout name", &state.loadout_name_input)
+            .on_input(Message::LoadoutNameChanged)
+            .width(Length::Fill),
+        button("Save Loadout")
+            .style(button::secondary)
+            .on_press(Message::SaveLoadout),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center);
+
+    let mode_row: Element<'_, Message> = match &state.loadout_selection {
+        Some(selection) => {
+            let count = text(format!("Selected {}/{}", selection.len(), max_prepared));
+            let count = if selection.len() as i32 > max_prepared {
+                count.style(text::danger)
+            } else {
+                count
+            };
+            row![
+                count,
+                Space::new().width(Length::Fill),
+                button("Cancel")
+                    .style(button::secondary)
+                    .on_press(Message::CancelLoadoutSelection),
+                button("Prepare Selection").on_press_maybe(
+                    (selection.len() as i32 <= max_prepared).then_some(Message::PrepareSelection)
+                ),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .into()
+        }
+        None => button("Swap Loadout")
+            .style(button::secondary)
+            .on_press(Message::StartLoadoutSelection)
+            .into(),
+    };
+
+    column![saved, save_row, mode_row].spacing(10).into()
+}